@@ -8,8 +8,23 @@
 use std::time::Duration;
 use std::time::Instant;
 
+use sync::Mutex;
+
 use crate::descriptor::AsRawDescriptor;
 use crate::Event;
+use crate::EventWaitResult;
+use crate::Result;
+use crate::WaitContext;
+
+/// A source of time that `Event::wait_timeout_with_clock` measures its timeout with.
+///
+/// `Clock` waits on the real monotonic time and is what `Event::wait_timeout` uses. A
+/// `Mutex<FakeClock>` only lets the timeout expire once the fake time is advanced past it.
+pub trait WaitClock {
+    /// Blocks until `event` is signaled and clears the signal, or until `timeout` has passed
+    /// according to this clock.
+    fn wait_event(&self, event: &Event, timeout: Duration) -> Result<EventWaitResult>;
+}
 
 #[derive(Debug, Default, Copy, Clone)]
 pub struct Clock {}
@@ -23,6 +38,12 @@ impl Clock {
     }
 }
 
+impl WaitClock for Clock {
+    fn wait_event(&self, event: &Event, timeout: Duration) -> Result<EventWaitResult> {
+        event.0.wait_timeout(timeout)
+    }
+}
+
 /// A fake clock that can be used in tests to give exact control over the time.
 /// For a code example, see the tests in base/src/timer.rs.
 #[derive(Debug)]
@@ -60,6 +81,17 @@ impl FakeClock {
         self.deadlines.push((deadline_ns, descriptor));
     }
 
+    /// Unregister any event that refers to the same event as |event|, including clones of it.
+    pub fn remove_event(&mut self, event: &Event) {
+        self.deadlines
+            .retain(|(_, old_descriptor)| old_descriptor != event);
+    }
+
+    /// Returns how many registered events have not reached their deadline yet.
+    pub fn pending_events(&self) -> usize {
+        self.deadlines.len()
+    }
+
     pub fn add_ns(&mut self, ns: u64) {
         self.ns_since_epoch += ns;
         let time = self.ns_since_epoch;
@@ -78,3 +110,34 @@ impl Default for FakeClock {
         Self::new()
     }
 }
+
+impl WaitClock for Mutex<FakeClock> {
+    /// A zero timeout never waits for the clock. A timeout past the end of the clock's range
+    /// never expires.
+    fn wait_event(&self, event: &Event, timeout: Duration) -> Result<EventWaitResult> {
+        if timeout.is_zero() {
+            return event.0.wait_timeout(timeout);
+        }
+
+        let deadline = Event::new()?;
+        {
+            let mut clock = self.lock();
+            let timeout_ns = u64::try_from(timeout.as_nanos()).unwrap_or(u64::MAX);
+            let deadline_ns = clock.nanos().saturating_add(timeout_ns);
+            clock.add_event(deadline_ns, deadline.try_clone()?);
+        }
+
+        const SIGNALED: u8 = 0;
+        const TIMED_OUT: u8 = 1;
+        let wait_ctx = WaitContext::build_with(&[(event, SIGNALED), (&deadline, TIMED_OUT)])?;
+        let signaled = wait_ctx.wait()?.iter().any(|e| e.token == SIGNALED);
+        // Stop the clock from holding on to the deadline event if it has not expired yet.
+        self.lock().remove_event(&deadline);
+        if signaled {
+            event.wait()?;
+            Ok(EventWaitResult::Signaled)
+        } else {
+            Ok(EventWaitResult::TimedOut)
+        }
+    }
+}
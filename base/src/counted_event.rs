@@ -0,0 +1,108 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Event carrying an exact count of work items from any number of producers.
+
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::descriptor::AsRawDescriptor;
+use crate::Event;
+use crate::RawDescriptor;
+use crate::Result;
+
+/// An `Event` paired with a count kept in memory rather than in the kernel.
+///
+/// Producers add to the count and signal the event, which is only used to wake the consumer, so
+/// the total is exact however fast producers are and whatever the limits of the underlying event.
+/// The count saturates at `u64::MAX`. Clones made with `try_clone` share the event and the count.
+#[derive(Debug)]
+pub struct CountedEvent {
+    event: Event,
+    count: Arc<AtomicU64>,
+}
+
+impl CountedEvent {
+    /// Creates a new `CountedEvent` with a count of 0.
+    pub fn new() -> Result<CountedEvent> {
+        Ok(CountedEvent {
+            event: Event::new()?,
+            count: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    /// Clones this event, sharing both the event and the count.
+    pub fn try_clone(&self) -> Result<CountedEvent> {
+        Ok(CountedEvent {
+            event: self.event.try_clone()?,
+            count: self.count.clone(),
+        })
+    }
+
+    /// Adds `n` to the count and wakes the consumer.
+    pub fn add(&self, n: u64) -> Result<()> {
+        // The closure always returns `Some`, so this can't fail.
+        let _ = self
+            .count
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                Some(count.saturating_add(n))
+            });
+        self.event.signal()
+    }
+
+    /// Blocks until the count is non-zero, then resets it to 0 and returns what it was.
+    pub fn read(&self) -> Result<u64> {
+        loop {
+            self.event.wait()?;
+            // Producers add to the count before signaling, so a wakeup whose count was already
+            // taken by the previous `read` leaves 0 here.
+            let count = self.count.swap(0, Ordering::AcqRel);
+            if count != 0 {
+                return Ok(count);
+            }
+        }
+    }
+}
+
+impl AsRawDescriptor for CountedEvent {
+    fn as_raw_descriptor(&self) -> RawDescriptor {
+        self.event.as_raw_descriptor()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn many_producers() {
+        const PRODUCERS: u64 = 8;
+        const ADDS: u64 = 1000;
+
+        let counted = CountedEvent::new().unwrap();
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|_| {
+                let producer = counted.try_clone().unwrap();
+                thread::spawn(move || {
+                    for _ in 0..ADDS {
+                        producer.add(1).unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        let mut total = 0;
+        while total < PRODUCERS * ADDS {
+            total += counted.read().unwrap();
+        }
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        assert_eq!(total, PRODUCERS * ADDS);
+        assert_eq!(counted.count.load(Ordering::Acquire), 0);
+    }
+}
@@ -12,8 +12,10 @@ use crate::descriptor::FromRawDescriptor;
 use crate::descriptor::IntoRawDescriptor;
 use crate::descriptor::SafeDescriptor;
 use crate::platform::PlatformEvent;
+use crate::Clock;
 use crate::RawDescriptor;
 use crate::Result;
+use crate::WaitClock;
 
 /// An inter-process event wait/notify mechanism. Loosely speaking: Writes signal the event. Reads
 /// block until the event is signaled and then clear the signal.
@@ -66,7 +68,17 @@ impl Event {
     /// It is undefined behavior to wait on an event from multiple threads or processes
     /// simultaneously.
     pub fn wait_timeout(&self, timeout: Duration) -> Result<EventWaitResult> {
-        self.0.wait_timeout(timeout)
+        self.wait_timeout_with_clock(&Clock::new(), timeout)
+    }
+
+    /// Like `wait_timeout`, but the timeout is measured by `clock`. With a `Mutex<FakeClock>`,
+    /// tests can control exactly when the timeout expires by advancing the clock.
+    pub fn wait_timeout_with_clock<C: WaitClock + ?Sized>(
+        &self,
+        clock: &C,
+        timeout: Duration,
+    ) -> Result<EventWaitResult> {
+        clock.wait_event(self, timeout)
     }
 
     /// Clears the event without blocking.
@@ -88,6 +100,14 @@ impl Event {
     pub fn try_clone(&self) -> Result<Event> {
         self.0.try_clone().map(Event)
     }
+
+    /// Creates a new event in an unsignaled state and returns two handles to it, e.g. one to name
+    /// the end that signals and one the end that waits. The state is shared as with `try_clone`.
+    pub fn pair() -> Result<(Event, Event)> {
+        let event = Event::new()?;
+        let other = event.try_clone()?;
+        Ok((event, other))
+    }
 }
 
 impl AsRawDescriptor for Event {
@@ -119,3 +139,103 @@ impl From<SafeDescriptor> for Event {
         Event(PlatformEvent::from(sd))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::sync::Arc;
+    use std::thread;
+
+    use sync::Mutex;
+
+    use super::*;
+    use crate::FakeClock;
+
+    #[test]
+    fn wait_timeout_with_clock() {
+        let clock = Arc::new(Mutex::new(FakeClock::new()));
+        let evt = Event::new().unwrap();
+
+        evt.signal().unwrap();
+        assert_eq!(
+            evt.wait_timeout_with_clock(&*clock, Duration::from_nanos(100)),
+            Ok(EventWaitResult::Signaled)
+        );
+        // The deadline is unregistered once the event is signaled.
+        assert_eq!(clock.lock().pending_events(), 0);
+        assert_eq!(
+            evt.wait_timeout_with_clock(&*clock, Duration::ZERO),
+            Ok(EventWaitResult::TimedOut)
+        );
+
+        let (tx, rx) = mpsc::channel();
+        let waiter_clock = clock.clone();
+        let waiter = thread::spawn(move || {
+            let result = evt.wait_timeout_with_clock(&*waiter_clock, Duration::from_nanos(100));
+            tx.send(result).unwrap();
+        });
+        // Wait for the waiter to register its deadline with the clock.
+        while clock.lock().pending_events() == 0 {
+            thread::yield_now();
+        }
+        clock.lock().add_ns(99);
+        thread::sleep(Duration::from_millis(10));
+        assert!(rx.try_recv().is_err());
+
+        clock.lock().add_ns(1);
+        assert_eq!(rx.recv().unwrap(), Ok(EventWaitResult::TimedOut));
+        waiter.join().unwrap();
+    }
+
+    #[test]
+    fn wait_timeout_with_clock_max() {
+        let clock = Mutex::new(FakeClock::new());
+        clock.lock().add_ns(1);
+        let evt = Event::new().unwrap();
+
+        evt.signal().unwrap();
+        assert_eq!(
+            evt.wait_timeout_with_clock(&clock, Duration::MAX),
+            Ok(EventWaitResult::Signaled)
+        );
+        assert_eq!(clock.lock().pending_events(), 0);
+    }
+
+    #[test]
+    fn pair() {
+        let (signal, wait) = Event::pair().unwrap();
+        assert_eq!(
+            wait.wait_timeout(Duration::ZERO),
+            Ok(EventWaitResult::TimedOut)
+        );
+
+        signal.signal().unwrap();
+        assert_eq!(
+            wait.wait_timeout(Duration::ZERO),
+            Ok(EventWaitResult::Signaled)
+        );
+        // Waiting on one end clears the signal for both.
+        assert_eq!(
+            signal.wait_timeout(Duration::ZERO),
+            Ok(EventWaitResult::TimedOut)
+        );
+    }
+
+    #[test]
+    fn from_safe_descriptor() {
+        let evt = Event::new().unwrap();
+        let desc = SafeDescriptor::from(evt.try_clone().unwrap());
+        let evt_from_desc = Event::from(desc);
+
+        evt_from_desc.signal().unwrap();
+        assert_eq!(
+            evt.wait_timeout(Duration::ZERO),
+            Ok(EventWaitResult::Signaled)
+        );
+        evt.signal().unwrap();
+        assert_eq!(
+            evt_from_desc.wait_timeout(Duration::ZERO),
+            Ok(EventWaitResult::Signaled)
+        );
+    }
+}
@@ -6,6 +6,7 @@
 
 mod alloc;
 mod clock;
+mod counted_event;
 pub mod custom_serde;
 pub mod descriptor;
 pub mod descriptor_reflection;
@@ -15,10 +16,14 @@ mod file_traits;
 mod iobuf;
 mod mmap;
 mod notifiers;
+mod oneshot;
+mod rate_limiter;
 mod shm;
+mod shutdown_coordinator;
 pub mod syslog;
 pub mod test_utils;
 mod timer;
+mod timer_wheel;
 mod tube;
 mod volatile_memory;
 mod wait_context;
@@ -30,6 +35,8 @@ pub use alloc::LayoutAllocation;
 
 pub use clock::Clock;
 pub use clock::FakeClock;
+pub use clock::WaitClock;
+pub use counted_event::CountedEvent;
 pub use errno::errno_result;
 pub use errno::Error;
 pub use errno::Result;
@@ -50,6 +57,7 @@ pub use mmap::MemoryMappingBuilder;
 pub use mmap::Result as MmapResult;
 pub use notifiers::CloseNotifier;
 pub use notifiers::ReadNotifier;
+pub use oneshot::OneShot;
 pub use platform::ioctl::ioctl;
 pub use platform::ioctl::ioctl_with_mut_ptr;
 pub use platform::ioctl::ioctl_with_mut_ref;
@@ -57,11 +65,15 @@ pub use platform::ioctl::ioctl_with_ptr;
 pub use platform::ioctl::ioctl_with_ref;
 pub use platform::ioctl::ioctl_with_val;
 pub use platform::ioctl::IoctlNr;
+pub use rate_limiter::RateLimiter;
 pub use shm::SharedMemory;
+pub use shutdown_coordinator::ShutdownCoordinator;
+pub use shutdown_coordinator::ShutdownHandle;
 use sys::platform;
 pub use timer::FakeTimer;
 pub use timer::Timer;
 pub use timer::TimerTrait;
+pub use timer_wheel::TimerWheel;
 pub use tube::Error as TubeError;
 #[cfg(any(windows, feature = "proto_tube"))]
 pub use tube::ProtoTube;
@@ -114,7 +126,12 @@ cfg_if::cfg_if! {
         pub use linux::sched_setattr;
         pub use linux::UnlinkUnixListener;
         pub use linux::EventExt;
+        pub use linux::EventInfo;
+        pub use linux::FairEvent;
         pub use linux::Gid;
+        pub use linux::HintedEvent;
+        #[cfg(feature = "tokio")]
+        pub use linux::TokioEvent;
     }
 }
 
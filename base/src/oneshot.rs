@@ -0,0 +1,90 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! One-time notification built on `Event`.
+
+use std::time::Duration;
+
+use crate::Event;
+use crate::Result;
+use crate::WaitContext;
+
+/// A notification that fires once and then stays fired, such as a shutdown request.
+///
+/// Unlike a plain `Event`, any number of waiters may wait on clones of a `OneShot`: waiting only
+/// checks whether the underlying event is signaled, without clearing it, so every waiter observes
+/// the signal.
+#[derive(Debug)]
+pub struct OneShot {
+    event: Event,
+}
+
+impl OneShot {
+    /// Creates a new `OneShot` that has not fired.
+    pub fn new() -> Result<OneShot> {
+        Ok(OneShot {
+            event: Event::new()?,
+        })
+    }
+
+    /// Creates another handle to the same notification, to signal or wait on it from elsewhere.
+    pub fn try_clone(&self) -> Result<OneShot> {
+        Ok(OneShot {
+            event: self.event.try_clone()?,
+        })
+    }
+
+    /// Fires the notification. This consumes the handle, so a handle can only fire it once.
+    pub fn signal(self) -> Result<()> {
+        self.event.signal()
+    }
+
+    /// Blocks until the notification has fired.
+    pub fn wait(&self) -> Result<()> {
+        self.wait_context()?.wait()?;
+        Ok(())
+    }
+
+    /// Returns whether the notification has fired, without blocking.
+    pub fn try_wait(&self) -> Result<bool> {
+        Ok(!self
+            .wait_context()?
+            .wait_timeout(Duration::ZERO)?
+            .is_empty())
+    }
+
+    fn wait_context(&self) -> Result<WaitContext<()>> {
+        WaitContext::build_with(&[(&self.event, ())])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn all_waiters_observe_signal() {
+        let oneshot = OneShot::new().unwrap();
+        assert!(!oneshot.try_wait().unwrap());
+
+        let waiters: Vec<_> = (0..4)
+            .map(|_| {
+                let waiter = oneshot.try_clone().unwrap();
+                thread::spawn(move || waiter.wait().unwrap())
+            })
+            .collect();
+
+        let observer = oneshot.try_clone().unwrap();
+        oneshot.signal().unwrap();
+        for waiter in waiters {
+            waiter.join().unwrap();
+        }
+
+        // Waiting again returns immediately.
+        observer.wait().unwrap();
+        assert!(observer.try_wait().unwrap());
+    }
+}
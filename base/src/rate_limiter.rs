@@ -0,0 +1,188 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Token bucket rate limiter with an event that becomes readable when tokens are replenished.
+
+use std::time::Duration;
+use std::time::Instant;
+
+use sync::Mutex;
+
+use crate::descriptor::AsRawDescriptor;
+use crate::error;
+use crate::Error;
+use crate::Event;
+use crate::RawDescriptor;
+use crate::Result;
+use crate::SafeDescriptor;
+use crate::Timer;
+use crate::TimerTrait;
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// A token bucket holding up to `burst` tokens, refilled with `rate` tokens per second.
+///
+/// When `consume` fails for lack of tokens, the limiter's `event` becomes readable once enough
+/// tokens for that request have been added, so a worker can wait on it or add the limiter to its
+/// `WaitContext` instead of polling. The next `consume` makes it unreadable again. Another consumer
+/// may take the tokens first, so `consume` should be retried whenever the descriptor is readable
+/// and waited on again if it fails.
+pub struct RateLimiter {
+    rate: u64,
+    burst: u64,
+    bucket: Mutex<Bucket>,
+    // Armed for when a failed `consume` will have enough tokens.
+    timer: Mutex<Timer>,
+    // Shares the timer's descriptor, so it becomes readable when the timer expires.
+    event: Event,
+}
+
+struct Bucket {
+    tokens: u64,
+    // Time up to which tokens have been added. Only whole tokens are added, so this lags behind
+    // the current time by the time spent towards the next token.
+    last_refill: Instant,
+    // Whether the timer was armed by a failed `consume` and not cleared since.
+    timer_armed: bool,
+}
+
+impl Bucket {
+    fn refill(&mut self, rate: u64, burst: u64) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_nanos();
+        let new_tokens = elapsed * u128::from(rate) / NANOS_PER_SEC;
+        if new_tokens == 0 {
+            return;
+        }
+        let tokens = u128::from(self.tokens) + new_tokens;
+        if tokens >= u128::from(burst) {
+            self.tokens = burst;
+            self.last_refill = now;
+        } else {
+            self.tokens = tokens as u64;
+            self.last_refill +=
+                Duration::from_nanos((new_tokens * NANOS_PER_SEC / u128::from(rate)) as u64);
+        }
+    }
+}
+
+impl RateLimiter {
+    /// Creates a rate limiter that starts with a full bucket of `burst` tokens and adds `rate`
+    /// tokens per second. Fails with `EINVAL` if `rate` or `burst` is 0.
+    pub fn new(rate: u64, burst: u64) -> Result<RateLimiter> {
+        if rate == 0 || burst == 0 {
+            return Err(Error::new(libc::EINVAL));
+        }
+        let timer = Timer::new()?;
+        let event = Event::from(SafeDescriptor::try_from(&timer as &dyn AsRawDescriptor)?);
+        Ok(RateLimiter {
+            rate,
+            burst,
+            bucket: Mutex::new(Bucket {
+                tokens: burst,
+                last_refill: Instant::now(),
+                timer_armed: false,
+            }),
+            timer: Mutex::new(timer),
+            event,
+        })
+    }
+
+    /// Returns the event that becomes readable when tokens have been replenished after a failed
+    /// `consume`. It is backed by the limiter's timer, so it may be waited on but not signaled.
+    pub fn event(&self) -> &Event {
+        &self.event
+    }
+
+    /// Takes `n` tokens if there are that many and returns true. Otherwise takes nothing, arranges
+    /// for the limiter's event to become readable once there are enough tokens, and returns
+    /// false.
+    ///
+    /// A request for more than `burst` tokens never succeeds; the event then becomes readable
+    /// once the bucket is full.
+    pub fn consume(&self, n: u64) -> bool {
+        let mut bucket = self.bucket.lock();
+        bucket.refill(self.rate, self.burst);
+        if bucket.tokens >= n {
+            bucket.tokens -= n;
+            if bucket.timer_armed {
+                // Clearing the timer also makes its descriptor unreadable if it already expired.
+                if let Err(e) = self.timer.lock().clear() {
+                    error!("failed to clear rate limiter timer: {}", e);
+                }
+                bucket.timer_armed = false;
+            }
+            return true;
+        }
+
+        let missing = u128::from(n.min(self.burst) - bucket.tokens);
+        let needed = (missing * NANOS_PER_SEC).div_ceil(u128::from(self.rate));
+        let elapsed = bucket.last_refill.elapsed().as_nanos();
+        // A zero duration would disarm the timer.
+        let wait = needed.saturating_sub(elapsed).max(1);
+        let wait = Duration::from_nanos(u64::try_from(wait).unwrap_or(u64::MAX));
+        // Rearming the timer also makes its descriptor unreadable if it already expired.
+        if let Err(e) = self.timer.lock().reset(wait, None) {
+            error!("failed to arm rate limiter timer: {}", e);
+        }
+        bucket.timer_armed = true;
+        false
+    }
+}
+
+impl AsRawDescriptor for RateLimiter {
+    /// Returns the descriptor that becomes readable when tokens have been replenished after a
+    /// failed `consume`.
+    fn as_raw_descriptor(&self) -> RawDescriptor {
+        self.event.as_raw_descriptor()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EventWaitResult;
+    use crate::WaitContext;
+
+    #[test]
+    fn consume_waits_for_refill() {
+        // One token every 50ms, so none is added while the bucket is emptied.
+        let limiter = RateLimiter::new(20, 4).unwrap();
+        let wait_ctx = WaitContext::build_with(&[(&limiter, 0u32)]).unwrap();
+        assert!(limiter.consume(3));
+        assert!(limiter.consume(1));
+        assert!(!limiter.consume(1));
+
+        wait_ctx.wait().unwrap();
+        assert!(limiter.consume(1));
+        // Consuming makes the descriptor unreadable again.
+        assert!(wait_ctx.wait_timeout(Duration::ZERO).unwrap().is_empty());
+    }
+
+    #[test]
+    fn event_fires_on_refill() {
+        let limiter = RateLimiter::new(20, 2).unwrap();
+        assert!(limiter.consume(2));
+        assert!(!limiter.consume(1));
+        assert_eq!(
+            limiter.event().wait_timeout(Duration::ZERO).unwrap(),
+            EventWaitResult::TimedOut
+        );
+
+        assert_eq!(
+            limiter
+                .event()
+                .wait_timeout(Duration::from_secs(5))
+                .unwrap(),
+            EventWaitResult::Signaled
+        );
+        assert!(limiter.consume(1));
+    }
+
+    #[test]
+    fn new_invalid() {
+        assert!(RateLimiter::new(0, 1).is_err());
+        assert!(RateLimiter::new(1, 0).is_err());
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Stopping a set of workers and waiting for each of them to acknowledge.
+
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::Event;
+use crate::EventWaitResult;
+use crate::Result;
+
+/// Hands out a kill event and an acknowledgement event to each worker, so that all of them can be
+/// told to stop at once and waited on with a single deadline.
+#[derive(Default)]
+pub struct ShutdownCoordinator {
+    workers: Vec<ShutdownHandle>,
+}
+
+/// The events of one worker registered with a `ShutdownCoordinator`.
+pub struct ShutdownHandle {
+    kill_evt: Event,
+    ack_evt: Event,
+}
+
+impl ShutdownHandle {
+    /// Returns the event signaled when the worker should stop.
+    pub fn kill_evt(&self) -> &Event {
+        &self.kill_evt
+    }
+
+    /// Tells the coordinator that the worker has stopped.
+    pub fn ack(&self) -> Result<()> {
+        self.ack_evt.signal()
+    }
+}
+
+impl ShutdownCoordinator {
+    /// Creates a `ShutdownCoordinator` without any workers.
+    pub fn new() -> ShutdownCoordinator {
+        ShutdownCoordinator::default()
+    }
+
+    /// Registers a worker, returning the handle it should wait on and acknowledge with.
+    pub fn add_worker(&mut self) -> Result<ShutdownHandle> {
+        let kill_evt = Event::new()?;
+        let ack_evt = Event::new()?;
+        self.workers.push(ShutdownHandle {
+            kill_evt: kill_evt.try_clone()?,
+            ack_evt: ack_evt.try_clone()?,
+        });
+        Ok(ShutdownHandle { kill_evt, ack_evt })
+    }
+
+    /// Signals the kill event of every worker, then waits until all of them have acknowledged or
+    /// `timeout` has passed. Returns whether every worker acknowledged in time.
+    pub fn shutdown_all(&self, timeout: Duration) -> Result<bool> {
+        for worker in &self.workers {
+            worker.kill_evt.signal()?;
+        }
+
+        let deadline = Instant::now() + timeout;
+        for worker in &self.workers {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if worker.ack_evt.wait_timeout(remaining)? == EventWaitResult::TimedOut {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn all_workers_ack() {
+        const WORKERS: u64 = 4;
+
+        let mut coordinator = ShutdownCoordinator::new();
+        let workers: Vec<_> = (0..WORKERS)
+            .map(|i| {
+                let handle = coordinator.add_worker().unwrap();
+                thread::spawn(move || {
+                    handle.kill_evt().wait().unwrap();
+                    // Some workers take a little while to stop.
+                    thread::sleep(Duration::from_millis(5 * i));
+                    handle.ack().unwrap();
+                })
+            })
+            .collect();
+
+        assert_eq!(coordinator.shutdown_all(Duration::from_secs(5)), Ok(true));
+        for worker in workers {
+            worker.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn worker_without_ack_times_out() {
+        let mut coordinator = ShutdownCoordinator::new();
+        let acking = coordinator.add_worker().unwrap();
+        let _silent = coordinator.add_worker().unwrap();
+        acking.ack().unwrap();
+
+        assert_eq!(
+            coordinator.shutdown_all(Duration::from_millis(10)),
+            Ok(false)
+        );
+    }
+}
@@ -3,7 +3,12 @@
 // found in the LICENSE file.
 
 use std::mem;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::io::RawFd;
 use std::ptr;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 use std::time::Duration;
 
 use libc::c_void;
@@ -15,12 +20,14 @@ use serde::Deserialize;
 use serde::Serialize;
 
 use super::errno_result;
+use super::Error;
 use super::RawDescriptor;
 use super::Result;
 use crate::descriptor::AsRawDescriptor;
 use crate::descriptor::FromRawDescriptor;
 use crate::descriptor::IntoRawDescriptor;
 use crate::descriptor::SafeDescriptor;
+use crate::syscall;
 use crate::unix::duration_to_timespec;
 use crate::EventWaitResult;
 
@@ -28,18 +35,93 @@ use crate::EventWaitResult;
 ///
 /// An eventfd is useful because it is sendable across processes and can be used for signaling in
 /// and out of the KVM API. They can also be polled like any other file descriptor.
+///
+/// `PlatformEvent` is `Send` and `Sync` because it only holds the descriptor number, and every
+/// operation on it is a single read or write syscall that the kernel performs atomically. This is
+/// what allows an `Event` to be shared with or moved to other threads; see the
+/// `event_is_send_sync` test, which fails to build if a new field breaks it.
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(transparent)]
 pub(crate) struct PlatformEvent {
     event_handle: SafeDescriptor,
 }
 
+/// Descriptor details of an eventfd, returned by `EventExt::describe`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EventInfo {
+    /// The descriptor number.
+    pub descriptor: RawDescriptor,
+    /// Whether the descriptor is closed on exec (`FD_CLOEXEC`).
+    pub cloexec: bool,
+    /// Whether the eventfd is non-blocking (`O_NONBLOCK`).
+    pub nonblocking: bool,
+}
+
+/// Largest count an eventfd can hold.
+const MAX_EVENTFD_COUNT: u64 = u64::MAX - 1;
+
 /// Linux specific extensions to `Event`.
 pub trait EventExt {
     /// Adds `v` to the eventfd's count, blocking until this won't overflow the count.
     fn write_count(&self, v: u64) -> Result<()>;
     /// Blocks until the the eventfd's count is non-zero, then resets the count to zero.
     fn read_count(&self) -> Result<u64>;
+    /// Like `read_count`, but first checks up to `spin_iters` times whether the count is non-zero,
+    /// yielding the thread in between, before blocking.
+    ///
+    /// This trades CPU time for latency: a count written while spinning is read without the thread
+    /// being put to sleep and woken up again, but the thread keeps a CPU busy for as long as it
+    /// spins. It is only worth it on latency-sensitive paths where the count is usually written
+    /// shortly after, with `spin_iters` kept small.
+    fn read_spin(&self, spin_iters: usize) -> Result<u64>;
+    /// Blocks until at least `n` has been added to the eventfd's count, then consumes exactly `n`
+    /// of it. Any excess that was read is added back to the count.
+    ///
+    /// Each read of an eventfd returns and resets the whole count, so the count is accumulated over
+    /// as many reads as it takes to reach `n`. On an eventfd in semaphore mode (`EFD_SEMAPHORE`),
+    /// every read only consumes 1, so this would take `n` reads and never leave an excess.
+    fn read_at_least(&self, n: u64) -> Result<()>;
+    /// Adds `v` to the eventfd's count, clamping the count to its maximum instead of blocking.
+    ///
+    /// Unlike `write_count`, this does not keep an exact count: anything that does not fit is
+    /// dropped. This is meant for eventfds that are only used for signaling, where blocking the
+    /// signaling thread because nobody reads the eventfd would be worse than losing count. The
+    /// current count is taken and written back with `v` added, so this may still block if another
+    /// thread writes to the eventfd in between.
+    fn write_saturating(&self, v: u64) -> Result<()>;
+    /// Adds `v` to the eventfd's count like `write_count`, but returns `Ok(false)` instead of
+    /// blocking if the count would overflow, leaving it unchanged so the caller can decide whether
+    /// to drain it. Returns `Ok(true)` once `v` was added.
+    ///
+    /// Only an eventfd created with `EFD_NONBLOCK` can report that the write would block. On a
+    /// blocking eventfd, such as one made by `Event::new`, this blocks like `write_count` and
+    /// always returns `Ok(true)`. Fails with `EIO` if the write was short.
+    fn try_write(&self, v: u64) -> Result<bool>;
+    /// Waits until the eventfd's count is non-zero or `timeout` has passed, without reading it.
+    /// Returns whether the count is non-zero. A `timeout` of `None` waits indefinitely.
+    ///
+    /// This is for callers that need to check readiness, like `select`, before deciding whether
+    /// to read the count.
+    fn wait_readable(&self, timeout: Option<Duration>) -> Result<bool>;
+    /// Consumes the eventfd's whole count without blocking and returns it, or 0 if the count was
+    /// already zero.
+    ///
+    /// This reads until the eventfd is no longer readable, so it also works on an eventfd in
+    /// semaphore mode (`EFD_SEMAPHORE`), where every read only consumes 1. Anything written while
+    /// this runs may be included in the total.
+    fn drain(&self) -> Result<u64>;
+    /// Returns the descriptor number of this eventfd and its flags, without touching the count.
+    ///
+    /// This is meant for logging, e.g. to find out why a device that should have been woken up
+    /// wasn't, or which descriptors were leaked to a child process.
+    fn describe(&self) -> Result<EventInfo>;
+    /// Drains the eventfd like `drain`, then closes this descriptor and returns the drained count.
+    ///
+    /// This is for shutdown paths that should not leave a pending count behind or depend on drop
+    /// order. Clones made with `try_clone` keep the eventfd itself open.
+    fn close(self) -> Result<u64>
+    where
+        Self: Sized;
 }
 
 impl EventExt for crate::Event {
@@ -50,6 +132,52 @@ impl EventExt for crate::Event {
     fn read_count(&self) -> Result<u64> {
         self.0.read_count()
     }
+
+    fn read_spin(&self, spin_iters: usize) -> Result<u64> {
+        self.0.read_spin(spin_iters)
+    }
+
+    fn read_at_least(&self, n: u64) -> Result<()> {
+        self.0.read_at_least(n)
+    }
+
+    fn write_saturating(&self, v: u64) -> Result<()> {
+        self.0.write_saturating(v)
+    }
+
+    fn try_write(&self, v: u64) -> Result<bool> {
+        self.0.try_write(v)
+    }
+
+    fn wait_readable(&self, timeout: Option<Duration>) -> Result<bool> {
+        self.0.wait_readable(timeout)
+    }
+
+    fn drain(&self) -> Result<u64> {
+        self.0.drain()
+    }
+
+    fn describe(&self) -> Result<EventInfo> {
+        self.0.describe()
+    }
+
+    fn close(self) -> Result<u64> {
+        let count = self.drain()?;
+        let fd = self.into_raw_descriptor();
+        // SAFETY:
+        // This is safe because we own `fd`, which was just released from `self`, and nothing uses
+        // it afterwards.
+        if unsafe { libc::close(fd) } < 0 {
+            return errno_result();
+        }
+        Ok(count)
+    }
+}
+
+impl AsRawFd for crate::Event {
+    fn as_raw_fd(&self) -> RawFd {
+        self.as_raw_descriptor()
+    }
 }
 
 impl PlatformEvent {
@@ -107,6 +235,70 @@ impl PlatformEvent {
         Ok(buf)
     }
 
+    /// See `EventExt::read_spin`.
+    pub fn read_spin(&self, spin_iters: usize) -> Result<u64> {
+        for _ in 0..spin_iters {
+            if self.wait_readable(Some(Duration::ZERO))? {
+                return self.read_count();
+            }
+            std::thread::yield_now();
+        }
+        self.read_count()
+    }
+
+    /// See `EventExt::read_at_least`.
+    pub fn read_at_least(&self, n: u64) -> Result<()> {
+        let mut total: u64 = 0;
+        while total < n {
+            total = total.saturating_add(self.read_count()?);
+        }
+        let excess = total - n;
+        if excess > 0 {
+            self.write_count(excess)?;
+        }
+        Ok(())
+    }
+
+    /// See `EventExt::write_saturating`.
+    pub fn write_saturating(&self, v: u64) -> Result<()> {
+        let count = if self.wait_readable(Some(Duration::ZERO))? {
+            self.read_count()?
+        } else {
+            0
+        };
+        let count = count.saturating_add(v).min(MAX_EVENTFD_COUNT);
+        if count == 0 {
+            return Ok(());
+        }
+        self.write_count(count)
+    }
+
+    /// See `EventExt::try_write`.
+    pub fn try_write(&self, v: u64) -> Result<bool> {
+        // SAFETY:
+        // This is safe because we made this fd and the pointer we pass can not overflow because we
+        // give the syscall's size parameter properly.
+        let ret = unsafe {
+            write(
+                self.as_raw_descriptor(),
+                &v as *const u64 as *const c_void,
+                mem::size_of::<u64>(),
+            )
+        };
+        if ret < 0 {
+            let err = Error::last();
+            if err.errno() == libc::EAGAIN {
+                return Ok(false);
+            }
+            return Err(err);
+        }
+        // An eventfd takes all 8 bytes or none, so anything else leaves errno unset.
+        if ret as usize != mem::size_of::<u64>() {
+            return Err(Error::new(libc::EIO));
+        }
+        Ok(true)
+    }
+
     /// See `Event::signal`.
     pub fn signal(&self) -> Result<()> {
         self.write_count(1)
@@ -119,19 +311,31 @@ impl PlatformEvent {
 
     /// See `Event::wait_timeout`.
     pub fn wait_timeout(&self, timeout: Duration) -> Result<EventWaitResult> {
+        if !self.wait_readable(Some(timeout))? {
+            return Ok(EventWaitResult::TimedOut);
+        }
+
+        self.wait()?;
+        Ok(EventWaitResult::Signaled)
+    }
+
+    /// See `EventExt::wait_readable`.
+    pub fn wait_readable(&self, timeout: Option<Duration>) -> Result<bool> {
         let mut pfd = libc::pollfd {
             fd: self.as_raw_descriptor(),
             events: POLLIN,
             revents: 0,
         };
-        let timeoutspec: libc::timespec = duration_to_timespec(timeout);
+        let timeoutspec: Option<libc::timespec> = timeout.map(duration_to_timespec);
         // SAFETY:
         // Safe because this only modifies |pfd| and we check the return value
         let ret = unsafe {
             libc::ppoll(
                 &mut pfd as *mut libc::pollfd,
                 1,
-                &timeoutspec,
+                timeoutspec
+                    .as_ref()
+                    .map_or(ptr::null(), |t| t as *const libc::timespec),
                 ptr::null_mut(),
             )
         };
@@ -140,12 +344,36 @@ impl PlatformEvent {
         }
 
         // no return events (revents) means we got a timeout
-        if pfd.revents == 0 {
-            return Ok(EventWaitResult::TimedOut);
+        Ok(pfd.revents != 0)
+    }
+
+    /// See `EventExt::drain`.
+    pub fn drain(&self) -> Result<u64> {
+        let mut total: u64 = 0;
+        while self.wait_readable(Some(Duration::ZERO))? {
+            total = total.saturating_add(self.read_count()?);
         }
+        Ok(total)
+    }
 
-        self.wait()?;
-        Ok(EventWaitResult::Signaled)
+    /// See `EventExt::describe`.
+    pub fn describe(&self) -> Result<EventInfo> {
+        let descriptor = self.as_raw_descriptor();
+        let fd_flags = syscall!(
+            // SAFETY:
+            // Safe because F_GETFD takes no third parameter and we check the return value.
+            unsafe { libc::fcntl(descriptor, libc::F_GETFD) }
+        )?;
+        let status_flags = syscall!(
+            // SAFETY:
+            // Safe because F_GETFL takes no third parameter and we check the return value.
+            unsafe { libc::fcntl(descriptor, libc::F_GETFL) }
+        )?;
+        Ok(EventInfo {
+            descriptor,
+            cloexec: fd_flags & libc::FD_CLOEXEC != 0,
+            nonblocking: status_flags & libc::O_NONBLOCK != 0,
+        })
     }
 
     /// See `Event::reset`.
@@ -199,8 +427,152 @@ impl From<SafeDescriptor> for PlatformEvent {
     }
 }
 
+/// An eventfd that holds at most one signal: signaling it again before it is read does nothing,
+/// instead of adding to the count. This is meant for coalescing notifications such as interrupts,
+/// where only whether there was a signal matters.
+///
+/// Only the first `signal` after a `read` writes to the eventfd; the others just check an atomic
+/// flag shared with clones made by `try_clone`. A `signal` racing with a `read` may be merged into
+/// the signal being read, so the reader has to look for work after `read` returns, not before.
+///
+/// This is unrelated to `base::SignalFd`, which receives POSIX signals through a signalfd.
+#[derive(Debug)]
+pub struct SignalFd {
+    event: crate::Event,
+    // Whether the eventfd was written and not read since.
+    pending: Arc<AtomicBool>,
+}
+
+impl SignalFd {
+    /// Creates a new `SignalFd` that is not signaled.
+    pub fn new() -> Result<SignalFd> {
+        Ok(SignalFd {
+            event: crate::Event::new()?,
+            pending: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Clones this event, sharing both the eventfd and the signaled state.
+    pub fn try_clone(&self) -> Result<SignalFd> {
+        Ok(SignalFd {
+            event: self.event.try_clone()?,
+            pending: self.pending.clone(),
+        })
+    }
+
+    /// Signals the event, unless it is already signaled.
+    pub fn signal(&self) -> Result<()> {
+        if self.pending.swap(true, Ordering::AcqRel) {
+            return Ok(());
+        }
+        let result = self.event.signal();
+        if result.is_err() {
+            self.pending.store(false, Ordering::Release);
+        }
+        result
+    }
+
+    /// Blocks until the event is signaled, then clears it and returns the eventfd's count. The
+    /// count is 1 unless a racing signal was merged into this read or something else writes to the
+    /// eventfd directly.
+    pub fn read(&self) -> Result<u64> {
+        // Clear the flag before reading so that a signal racing with the read writes the eventfd
+        // again rather than being swallowed. Waiting for the eventfd first makes sure the signal
+        // being read has already written it.
+        self.event.wait_readable(None)?;
+        self.pending.swap(false, Ordering::AcqRel);
+        let count = self.event.read_count()?;
+        if count > 1 {
+            // The write of a signal made after the flag was cleared was read as well, so nothing
+            // is pending anymore despite the flag it set.
+            self.pending.store(false, Ordering::Release);
+        }
+        Ok(count)
+    }
+}
+
+impl AsRawDescriptor for SignalFd {
+    fn as_raw_descriptor(&self) -> RawDescriptor {
+        self.event.as_raw_descriptor()
+    }
+}
+
+/// An eventfd that remembers whether it was last written to or read from, so that hot loops can
+/// skip signaling it when it is probably still signaled, without a syscall.
+///
+/// The hint is shared with clones made by `try_clone` and is only updated by the methods of this
+/// type. It does not see writes and reads made through other processes or other descriptors for
+/// the same eventfd, so it must not be used as the source of truth. The eventfd is not exposed as
+/// an `Event` for that reason.
+#[derive(Debug)]
+pub struct HintedEvent {
+    event: crate::Event,
+    // Whether the eventfd was last written to rather than read from.
+    signaled: Arc<AtomicBool>,
+}
+
+impl HintedEvent {
+    /// Creates a new `HintedEvent` that is not signaled.
+    pub fn new() -> Result<HintedEvent> {
+        Ok(HintedEvent {
+            event: crate::Event::new()?,
+            signaled: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Clones this event, sharing both the eventfd and the hint.
+    pub fn try_clone(&self) -> Result<HintedEvent> {
+        Ok(HintedEvent {
+            event: self.event.try_clone()?,
+            signaled: self.signaled.clone(),
+        })
+    }
+
+    /// Adds `v` to the eventfd's count, like `EventExt::write_count`.
+    pub fn write_count(&self, v: u64) -> Result<()> {
+        self.event.write_count(v)?;
+        self.signaled.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Signals the event, like `Event::signal`.
+    pub fn signal(&self) -> Result<()> {
+        self.write_count(1)
+    }
+
+    /// Blocks until the eventfd's count is non-zero, then reads and resets it, like
+    /// `EventExt::read_count`.
+    pub fn read_count(&self) -> Result<u64> {
+        let count = self.event.read_count()?;
+        self.signaled.store(false, Ordering::Relaxed);
+        Ok(count)
+    }
+
+    /// Waits until the eventfd's count is non-zero or `timeout` has passed, like
+    /// `EventExt::wait_readable`, and refreshes the hint with the result.
+    pub fn wait_readable(&self, timeout: Option<Duration>) -> Result<bool> {
+        let readable = self.event.wait_readable(timeout)?;
+        self.signaled.store(readable, Ordering::Relaxed);
+        Ok(readable)
+    }
+
+    /// Returns whether the eventfd was last written to rather than read from, as far as this
+    /// event and its clones know.
+    pub fn is_probably_signaled(&self) -> bool {
+        self.signaled.load(Ordering::Relaxed)
+    }
+}
+
+impl AsRawDescriptor for HintedEvent {
+    fn as_raw_descriptor(&self) -> RawDescriptor {
+        self.event.as_raw_descriptor()
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::sync::atomic::AtomicU64;
+
     use super::*;
     use crate::Event;
     use crate::EventExt;
@@ -210,6 +582,13 @@ mod tests {
         Event::new().unwrap();
     }
 
+    #[test]
+    fn event_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<PlatformEvent>();
+        assert_send_sync::<Event>();
+    }
+
     #[test]
     fn read_write() {
         let evt = Event::new().unwrap();
@@ -217,6 +596,234 @@ mod tests {
         assert_eq!(evt.read_count(), Ok(55));
     }
 
+    #[test]
+    fn pair_shares_count() {
+        let (signal, wait) = Event::pair().unwrap();
+        signal.write_count(3).unwrap();
+        signal.write_count(4).unwrap();
+        assert_eq!(wait.read_count(), Ok(7));
+    }
+
+    #[test]
+    fn read_spin() {
+        let evt = Event::new().unwrap();
+        evt.write_count(3).unwrap();
+        assert_eq!(evt.read_spin(0), Ok(3));
+
+        let evt_clone = evt.try_clone().unwrap();
+        let writer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(10));
+            evt_clone.write_count(5).unwrap();
+        });
+        // Never runs out of spins, so the count must be picked up while spinning.
+        assert_eq!(evt.read_spin(usize::MAX), Ok(5));
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn read_at_least() {
+        let evt = Event::new().unwrap();
+        let evt_clone = evt.try_clone().unwrap();
+        let writer = std::thread::spawn(move || {
+            evt_clone.write_count(2).unwrap();
+            std::thread::sleep(Duration::from_millis(10));
+            evt_clone.write_count(3).unwrap();
+        });
+        evt.read_at_least(5).unwrap();
+        writer.join().unwrap();
+        assert_eq!(
+            evt.wait_timeout(Duration::ZERO).unwrap(),
+            EventWaitResult::TimedOut
+        );
+    }
+
+    #[test]
+    fn read_at_least_keeps_excess() {
+        let evt = Event::new().unwrap();
+        evt.write_count(7).unwrap();
+        evt.read_at_least(5).unwrap();
+        assert_eq!(evt.read_count(), Ok(2));
+    }
+
+    #[test]
+    fn write_saturating() {
+        let evt = Event::new().unwrap();
+        // None of these would return with write_count, as the eventfd is never read.
+        evt.write_saturating(u64::MAX).unwrap();
+        for _ in 0..10 {
+            evt.write_saturating(1).unwrap();
+        }
+        evt.write_saturating(u64::MAX - 5).unwrap();
+        assert_eq!(evt.read_count(), Ok(MAX_EVENTFD_COUNT));
+
+        evt.write_saturating(3).unwrap();
+        evt.write_saturating(4).unwrap();
+        assert_eq!(evt.read_count(), Ok(7));
+    }
+
+    #[test]
+    fn try_write() {
+        // SAFETY:
+        // This is safe because eventfd merely allocated an eventfd for our process and we check
+        // the result.
+        let fd = unsafe { eventfd(0, libc::EFD_NONBLOCK) };
+        assert!(fd >= 0);
+        // SAFETY:
+        // This is safe because the eventfd was just created and is owned by nothing else.
+        let evt = unsafe { Event::from_raw_descriptor(fd) };
+        assert_eq!(evt.try_write(MAX_EVENTFD_COUNT - 1), Ok(true));
+        assert_eq!(evt.try_write(2), Ok(false));
+        assert_eq!(evt.try_write(1), Ok(true));
+        assert_eq!(evt.try_write(1), Ok(false));
+        assert_eq!(evt.read_count(), Ok(MAX_EVENTFD_COUNT));
+
+        let evt = Event::new().unwrap();
+        assert_eq!(evt.try_write(3), Ok(true));
+        assert_eq!(evt.read_count(), Ok(3));
+    }
+
+    #[test]
+    fn hinted_event() {
+        let evt = HintedEvent::new().unwrap();
+        let evt_clone = evt.try_clone().unwrap();
+        assert!(!evt.is_probably_signaled());
+        evt.signal().unwrap();
+        assert!(evt.is_probably_signaled());
+        assert!(evt_clone.is_probably_signaled());
+        assert_eq!(evt_clone.read_count(), Ok(1));
+        assert!(!evt.is_probably_signaled());
+        assert_eq!(evt.wait_readable(Some(Duration::ZERO)), Ok(false));
+        assert!(!evt.is_probably_signaled());
+    }
+
+    #[test]
+    fn wait_readable() {
+        let evt = Event::new().unwrap();
+        assert_eq!(evt.wait_readable(Some(Duration::from_millis(1))), Ok(false));
+
+        evt.write_count(2).unwrap();
+        assert_eq!(evt.wait_readable(Some(Duration::ZERO)), Ok(true));
+        assert_eq!(evt.wait_readable(None), Ok(true));
+        // The count is left for the caller to read.
+        assert_eq!(evt.read_count(), Ok(2));
+    }
+
+    #[test]
+    fn drain() {
+        let evt = Event::new().unwrap();
+        assert_eq!(evt.drain(), Ok(0));
+        evt.write_count(4).unwrap();
+        assert_eq!(evt.drain(), Ok(4));
+        assert_eq!(evt.wait_readable(Some(Duration::ZERO)), Ok(false));
+    }
+
+    #[test]
+    fn drain_semaphore() {
+        // SAFETY:
+        // This is safe because eventfd merely allocated an eventfd for our process and we check
+        // the result.
+        let fd = unsafe { eventfd(0, libc::EFD_SEMAPHORE) };
+        assert!(fd >= 0);
+        // SAFETY:
+        // This is safe because the eventfd was just created and is owned by nothing else.
+        let evt = unsafe { Event::from_raw_descriptor(fd) };
+        evt.write_count(4).unwrap();
+        assert_eq!(evt.drain(), Ok(4));
+        assert_eq!(evt.wait_readable(Some(Duration::ZERO)), Ok(false));
+    }
+
+    #[test]
+    fn describe() {
+        // SAFETY:
+        // This is safe because eventfd merely allocated an eventfd for our process and we check
+        // the result.
+        let fd = unsafe { eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+        assert!(fd >= 0);
+        // SAFETY:
+        // This is safe because the eventfd was just created and is owned by nothing else.
+        let evt = unsafe { Event::from_raw_descriptor(fd) };
+        evt.write_count(2).unwrap();
+        assert_eq!(
+            evt.describe(),
+            Ok(EventInfo {
+                descriptor: fd,
+                cloexec: true,
+                nonblocking: true,
+            })
+        );
+        // The count is left alone.
+        assert_eq!(evt.read_count(), Ok(2));
+
+        // SAFETY:
+        // This is safe because eventfd merely allocated an eventfd for our process and we check
+        // the result.
+        let fd = unsafe { eventfd(0, 0) };
+        assert!(fd >= 0);
+        // SAFETY:
+        // This is safe because the eventfd was just created and is owned by nothing else.
+        let evt = unsafe { Event::from_raw_descriptor(fd) };
+        let info = evt.describe().unwrap();
+        assert!(!info.cloexec);
+        assert!(!info.nonblocking);
+    }
+
+    #[test]
+    fn close() {
+        let evt = Event::new().unwrap();
+        let evt_clone = evt.try_clone().unwrap();
+        evt.write_count(3).unwrap();
+        assert_eq!(evt.close(), Ok(3));
+        assert_eq!(evt_clone.wait_readable(Some(Duration::ZERO)), Ok(false));
+    }
+
+    #[test]
+    fn signal_fd() {
+        let evt = SignalFd::new().unwrap();
+        let evt_clone = evt.try_clone().unwrap();
+        for _ in 0..10 {
+            evt.signal().unwrap();
+            evt_clone.signal().unwrap();
+        }
+        assert_eq!(evt.read(), Ok(1));
+
+        // Signaling works again once read.
+        evt_clone.signal().unwrap();
+        evt.signal().unwrap();
+        assert_eq!(evt_clone.read(), Ok(1));
+    }
+
+    #[test]
+    fn signal_fd_races_read() {
+        const SIGNALS: u64 = 100000;
+        let evt = SignalFd::new().unwrap();
+        let evt_clone = evt.try_clone().unwrap();
+        let last = Arc::new(AtomicU64::new(0));
+        let last_clone = last.clone();
+        let signaler = std::thread::spawn(move || {
+            for i in 1..=SIGNALS {
+                last_clone.store(i, Ordering::Release);
+                evt_clone.signal().unwrap();
+            }
+        });
+
+        // Every signal is seen by a read that returns after it.
+        while last.load(Ordering::Acquire) != SIGNALS {
+            assert_eq!(
+                evt.event.wait_readable(Some(Duration::from_secs(5))),
+                Ok(true)
+            );
+            evt.read().unwrap();
+        }
+        signaler.join().unwrap();
+
+        // Racing reads don't leave the event stuck signaled without a write to read.
+        while evt.event.wait_readable(Some(Duration::ZERO)) == Ok(true) {
+            evt.read().unwrap();
+        }
+        evt.signal().unwrap();
+        assert_eq!(evt.event.wait_readable(Some(Duration::ZERO)), Ok(true));
+    }
+
     #[test]
     fn clone() {
         let evt = Event::new().unwrap();
@@ -0,0 +1,144 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use std::sync::Arc;
+
+use sync::Mutex;
+
+use super::EventExt;
+use super::Result;
+use crate::error;
+use crate::Event;
+use crate::EventToken;
+use crate::WaitContext;
+use crate::WorkerThread;
+
+/// An event whose signals are handed out round-robin to registered waiters, instead of to
+/// whichever thread the kernel wakes first when several threads read one eventfd.
+///
+/// Each waiter gets its own `Event` from `register_waiter`. A dispatcher thread reads the count of
+/// the shared eventfd and adds 1 to the next waiter's event for every signal, so a waiter's count
+/// is the number of signals it was given. Signals made while there are no waiters are kept until
+/// the first one registers. Waiters stay registered for as long as the `FairEvent` exists.
+pub struct FairEvent {
+    event: Event,
+    dispatch: Arc<Mutex<Dispatch>>,
+    _dispatcher: WorkerThread<()>,
+}
+
+#[derive(Default)]
+struct Dispatch {
+    waiters: Vec<Event>,
+    // Index in `waiters` of the one to get the next signal.
+    next: usize,
+    // Signals received while there were no waiters.
+    pending: u64,
+}
+
+impl Dispatch {
+    fn deliver(&mut self, count: u64) -> Result<()> {
+        if self.waiters.is_empty() {
+            self.pending += count;
+            return Ok(());
+        }
+        for _ in 0..count {
+            self.waiters[self.next].signal()?;
+            self.next = (self.next + 1) % self.waiters.len();
+        }
+        Ok(())
+    }
+}
+
+impl FairEvent {
+    /// Creates a new `FairEvent` without waiters.
+    pub fn new() -> Result<FairEvent> {
+        let event = Event::new()?;
+        let dispatch = Arc::new(Mutex::new(Dispatch::default()));
+        let dispatcher_event = event.try_clone()?;
+        let dispatcher_dispatch = dispatch.clone();
+        let dispatcher = WorkerThread::start("fair_event", move |kill_evt| {
+            if let Err(e) = run_dispatcher(dispatcher_event, dispatcher_dispatch, kill_evt) {
+                error!("fair event dispatcher failed: {}", e);
+            }
+        });
+
+        Ok(FairEvent {
+            event,
+            dispatch,
+            _dispatcher: dispatcher,
+        })
+    }
+
+    /// Signals the event, waking the next waiter in turn.
+    pub fn signal(&self) -> Result<()> {
+        self.event.signal()
+    }
+
+    /// Registers a new waiter and returns the event its signals are added to. Waiters are given
+    /// signals in the order they registered.
+    pub fn register_waiter(&self) -> Result<Event> {
+        let waiter = Event::new()?;
+        let mut dispatch = self.dispatch.lock();
+        dispatch.waiters.push(waiter.try_clone()?);
+        let pending = std::mem::take(&mut dispatch.pending);
+        dispatch.deliver(pending)?;
+        Ok(waiter)
+    }
+}
+
+fn run_dispatcher(event: Event, dispatch: Arc<Mutex<Dispatch>>, kill_evt: Event) -> Result<()> {
+    #[derive(EventToken)]
+    enum Token {
+        Signal,
+        Kill,
+    }
+
+    let wait_ctx = WaitContext::build_with(&[(&event, Token::Signal), (&kill_evt, Token::Kill)])?;
+    loop {
+        for triggered in wait_ctx.wait()?.iter().filter(|e| e.is_readable) {
+            match triggered.token {
+                Token::Signal => {
+                    let count = event.read_count()?;
+                    dispatch.lock().deliver(count)?;
+                }
+                Token::Kill => return Ok(()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn signals_shared_round_robin() {
+        let fair = FairEvent::new().unwrap();
+        let waiters: Vec<Event> = (0..3).map(|_| fair.register_waiter().unwrap()).collect();
+        for _ in 0..300 {
+            fair.signal().unwrap();
+        }
+
+        for waiter in &waiters {
+            waiter.read_at_least(100).unwrap();
+            assert_eq!(waiter.wait_readable(Some(Duration::ZERO)), Ok(false));
+        }
+    }
+
+    #[test]
+    fn signals_kept_until_first_waiter() {
+        let fair = FairEvent::new().unwrap();
+        fair.signal().unwrap();
+        fair.signal().unwrap();
+        // Wait for the dispatcher to take the signals.
+        while fair.event.wait_readable(Some(Duration::ZERO)).unwrap() {
+            std::thread::yield_now();
+        }
+
+        let waiter = fair.register_waiter().unwrap();
+        assert_eq!(waiter.read_count(), Ok(2));
+    }
+}
@@ -21,7 +21,8 @@ pub mod syslog;
 mod acpi_event;
 mod capabilities;
 mod descriptor;
-mod event;
+pub mod event;
+mod fair_event;
 mod file;
 mod file_traits;
 mod get_filesystem_type;
@@ -39,6 +40,8 @@ pub mod signal;
 mod signalfd;
 mod terminal;
 mod timer;
+#[cfg(feature = "tokio")]
+mod tokio_event;
 pub mod vsock;
 mod write_zeroes;
 
@@ -63,7 +66,10 @@ pub use acpi_event::*;
 pub use capabilities::drop_capabilities;
 pub use descriptor::*;
 pub use event::EventExt;
+pub use event::EventInfo;
+pub use event::HintedEvent;
 pub(crate) use event::PlatformEvent;
+pub use fair_event::FairEvent;
 pub use file::find_next_data;
 pub use file::FileDataIterator;
 pub(crate) use file_traits::lib::*;
@@ -99,6 +105,8 @@ pub use signalfd::Error as SignalFdError;
 pub use signalfd::*;
 pub use terminal::*;
 pub use timer::*;
+#[cfg(feature = "tokio")]
+pub use tokio_event::TokioEvent;
 pub(crate) use write_zeroes::file_punch_hole;
 pub(crate) use write_zeroes::file_write_zeroes_at;
 
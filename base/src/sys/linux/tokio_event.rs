@@ -0,0 +1,102 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Awaiting an `Event` from a tokio runtime.
+
+use std::io;
+
+use tokio::io::unix::AsyncFd;
+use tokio::io::Interest;
+
+use super::Error;
+use super::EventExt;
+use super::Result;
+use crate::Event;
+
+/// An `Event` registered with the reactor of a tokio runtime, so that it can be read without
+/// blocking the runtime's threads.
+///
+/// The eventfd must be non-blocking, e.g. by setting `O_NONBLOCK` with `add_fd_flags`, which also
+/// makes clones of the event non-blocking.
+pub struct TokioEvent(AsyncFd<Event>);
+
+impl TokioEvent {
+    /// Registers `event` with the reactor of the current tokio runtime, which must have IO
+    /// enabled. Fails with `EINVAL` if `event` is blocking.
+    ///
+    /// Panics if not called from within a tokio runtime.
+    pub fn new(event: Event) -> Result<TokioEvent> {
+        if !event.describe()?.nonblocking {
+            return Err(Error::new(libc::EINVAL));
+        }
+        Ok(TokioEvent(AsyncFd::with_interest(
+            event,
+            Interest::READABLE,
+        )?))
+    }
+
+    /// Waits until the count is non-zero, then resets it to 0 and returns what it was.
+    pub async fn read(&self) -> Result<u64> {
+        loop {
+            let mut guard = self.0.readable().await?;
+            // Another reader may have taken the count since the eventfd was reported readable, in
+            // which case the read would block and the readiness is cleared to wait again.
+            if let Ok(result) =
+                guard.try_io(|event| event.get_ref().read_count().map_err(io::Error::from))
+            {
+                return Ok(result?);
+            }
+        }
+    }
+
+    /// Returns the wrapped event, e.g. to signal it.
+    pub fn get_ref(&self) -> &Event {
+        self.0.get_ref()
+    }
+
+    /// Deregisters the event from the reactor and returns it.
+    pub fn into_inner(self) -> Event {
+        self.0.into_inner()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+
+    use tokio::runtime::Builder;
+
+    use super::*;
+    use crate::add_fd_flags;
+    use crate::AsRawDescriptor;
+
+    #[test]
+    fn read_written_from_thread() {
+        let runtime = Builder::new_current_thread().enable_io().build().unwrap();
+        let event = Event::new().unwrap();
+        add_fd_flags(event.as_raw_descriptor(), libc::O_NONBLOCK).unwrap();
+        let writer_event = event.try_clone().unwrap();
+
+        runtime.block_on(async {
+            let event = TokioEvent::new(event).unwrap();
+            let writer = thread::spawn(move || {
+                thread::sleep(Duration::from_millis(10));
+                writer_event.write_count(42).unwrap();
+            });
+            assert_eq!(event.read().await, Ok(42));
+            writer.join().unwrap();
+        });
+    }
+
+    #[test]
+    fn blocking_event_rejected() {
+        let runtime = Builder::new_current_thread().enable_io().build().unwrap();
+        let _guard = runtime.enter();
+        assert_eq!(
+            TokioEvent::new(Event::new().unwrap()).err(),
+            Some(Error::new(libc::EINVAL))
+        );
+    }
+}
@@ -0,0 +1,242 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+//! Many timeouts multiplexed onto a single `Timer`.
+
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use sync::Mutex;
+
+use crate::error;
+use crate::Error;
+use crate::Event;
+use crate::EventToken;
+use crate::Result;
+use crate::Timer;
+use crate::TimerTrait;
+use crate::WaitContext;
+use crate::WorkerThread;
+
+/// Timeouts sharing one timer, each with its own `Event` that is signaled when it is due.
+///
+/// This lets devices that need occasional wakeups avoid creating a timer each. A worker thread
+/// arms the timer for the earliest deadline and signals the events of the timeouts that are due
+/// whenever it expires. A periodic timeout that falls behind by more than a period is signaled
+/// once and rescheduled a period from then, rather than once for every period missed.
+pub struct TimerWheel {
+    wheel: Arc<Mutex<Wheel>>,
+    // Signals the events of timeouts that are due when the timer expires.
+    _worker: WorkerThread<()>,
+}
+
+struct Wheel {
+    timer: Timer,
+    timeouts: Vec<Timeout>,
+}
+
+struct Timeout {
+    event: Event,
+    deadline: Instant,
+    period: Option<Duration>,
+}
+
+impl Wheel {
+    /// Signals the timeouts that are due, dropping the one-shot ones and rescheduling the others.
+    fn expire(&mut self) -> Result<()> {
+        let now = Instant::now();
+        let mut result = Ok(());
+        self.timeouts.retain_mut(|timeout| {
+            if timeout.deadline > now {
+                return true;
+            }
+            if let Err(e) = timeout.event.signal() {
+                result = Err(e);
+            }
+            match timeout.period {
+                Some(period) => {
+                    let next = match timeout.deadline.checked_add(period) {
+                        Some(next) if next > now => Some(next),
+                        _ => now.checked_add(period),
+                    };
+                    // A timeout whose next deadline can't be represented is never due again.
+                    match next {
+                        Some(next) => {
+                            timeout.deadline = next;
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                None => false,
+            }
+        });
+        result
+    }
+
+    /// Arms the timer for the earliest deadline, or disarms it if there are no timeouts.
+    fn arm(&mut self) -> Result<()> {
+        match self.timeouts.iter().map(|timeout| timeout.deadline).min() {
+            Some(deadline) => {
+                // A zero duration would disarm the timer.
+                let wait = deadline
+                    .saturating_duration_since(Instant::now())
+                    .max(Duration::from_nanos(1));
+                self.timer.reset(wait, None)
+            }
+            None => self.timer.clear(),
+        }
+    }
+}
+
+impl TimerWheel {
+    /// Creates a `TimerWheel` without any timeouts.
+    pub fn new() -> Result<TimerWheel> {
+        let timer = Timer::new()?;
+        let worker_timer = timer.try_clone()?;
+        let wheel = Arc::new(Mutex::new(Wheel {
+            timer,
+            timeouts: Vec::new(),
+        }));
+        let worker_wheel = wheel.clone();
+        let worker = WorkerThread::start("timer_wheel", move |kill_evt| {
+            if let Err(e) = run_worker(worker_timer, worker_wheel, kill_evt) {
+                error!("timer wheel worker failed: {}", e);
+            }
+        });
+
+        Ok(TimerWheel {
+            wheel,
+            _worker: worker,
+        })
+    }
+
+    /// Registers a timeout due after `duration`, and every `duration` after that if `periodic`.
+    /// Returns the event signaled each time it is due. Fails with `EINVAL` if `periodic` is set
+    /// and `duration` is zero, or if `duration` is too long for the deadline to be represented.
+    pub fn register(&self, duration: Duration, periodic: bool) -> Result<Event> {
+        if periodic && duration.is_zero() {
+            return Err(Error::new(libc::EINVAL));
+        }
+        let deadline = Instant::now()
+            .checked_add(duration)
+            .ok_or_else(|| Error::new(libc::EINVAL))?;
+        let event = Event::new()?;
+        let mut wheel = self.wheel.lock();
+        wheel.timeouts.push(Timeout {
+            event: event.try_clone()?,
+            deadline,
+            period: periodic.then_some(duration),
+        });
+        wheel.arm()?;
+        Ok(event)
+    }
+
+    /// Cancels the timeout whose event is `event`, or a clone of it, so that it is no longer
+    /// signaled. Does nothing if there is no such timeout, such as a one-shot timeout that was
+    /// already due.
+    pub fn cancel(&self, event: &Event) -> Result<()> {
+        let mut wheel = self.wheel.lock();
+        wheel.timeouts.retain(|timeout| timeout.event != *event);
+        wheel.arm()
+    }
+}
+
+fn run_worker(mut timer: Timer, wheel: Arc<Mutex<Wheel>>, kill_evt: Event) -> Result<()> {
+    #[derive(EventToken)]
+    enum Token {
+        Expired,
+        Kill,
+    }
+
+    let wait_ctx = WaitContext::build_with(&[(&timer, Token::Expired), (&kill_evt, Token::Kill)])?;
+    loop {
+        for triggered in wait_ctx.wait()?.iter().filter(|e| e.is_readable) {
+            match triggered.token {
+                Token::Expired => {
+                    let mut wheel = wheel.lock();
+                    // `register` or `cancel` may have rearmed or cleared the timer since the
+                    // wakeup, and reading it would then block until it expires again, if ever.
+                    // It can't change while the wheel is locked, so only read it if it is still
+                    // readable.
+                    let expired = wait_ctx
+                        .wait_timeout(Duration::ZERO)?
+                        .iter()
+                        .any(|e| e.is_readable && matches!(e.token, Token::Expired));
+                    if expired {
+                        timer.mark_waited()?;
+                    }
+                    wheel.expire()?;
+                    wheel.arm()?;
+                }
+                Token::Kill => return Ok(()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EventWaitResult;
+
+    #[test]
+    fn periodic_timeouts_keep_their_cadence() {
+        const FAST: Duration = Duration::from_millis(20);
+        const SLOW: Duration = Duration::from_millis(70);
+
+        let wheel = TimerWheel::new().unwrap();
+        let start = Instant::now();
+        let fast = wheel.register(FAST, true).unwrap();
+        let slow = wheel.register(SLOW, true).unwrap();
+
+        // Timers never fire early, so the n-th wakeup is at least n periods after the start.
+        for n in 1..=3 {
+            fast.wait().unwrap();
+            assert!(start.elapsed() >= FAST * n);
+        }
+        for n in 1..=2 {
+            slow.wait().unwrap();
+            assert!(start.elapsed() >= SLOW * n);
+        }
+
+        // The slow timeout keeps firing once the fast one is cancelled.
+        wheel.cancel(&fast).unwrap();
+        let _ = fast.wait_timeout(Duration::ZERO).unwrap();
+        slow.wait().unwrap();
+        assert!(start.elapsed() >= SLOW * 3);
+        assert_eq!(
+            fast.wait_timeout(Duration::ZERO).unwrap(),
+            EventWaitResult::TimedOut
+        );
+    }
+
+    #[test]
+    fn cancel_while_expiring() {
+        // Cancel at different points around the expiration, so that some cancellations land
+        // between the worker waking up and handling it.
+        for i in 0..1000 {
+            let wheel = TimerWheel::new().unwrap();
+            let event = wheel.register(Duration::from_micros(20), false).unwrap();
+            std::thread::sleep(Duration::from_micros(i % 40));
+            wheel.cancel(&event).unwrap();
+            // Dropping the wheel stops the worker, which must not be stuck reading the timer.
+            drop(wheel);
+        }
+    }
+
+    #[test]
+    fn register_invalid_duration() {
+        let wheel = TimerWheel::new().unwrap();
+        assert_eq!(
+            wheel.register(Duration::ZERO, true).err(),
+            Some(Error::new(libc::EINVAL))
+        );
+        assert_eq!(
+            wheel.register(Duration::MAX, false).err(),
+            Some(Error::new(libc::EINVAL))
+        );
+    }
+}
@@ -13,30 +13,49 @@ use quote::quote_spanned;
 use syn::parse::Error;
 use syn::parse::Result;
 use syn::parse_macro_input;
+use syn::parse_quote;
 use syn::Attribute;
 use syn::Data;
 use syn::DataEnum;
+use syn::DataStruct;
 use syn::DeriveInput;
 use syn::Fields;
 use syn::FieldsNamed;
 use syn::FieldsUnnamed;
+use syn::Generics;
 use syn::Ident;
 use syn::Lit;
 use syn::LitInt;
+use syn::LitStr;
 use syn::Meta;
 use syn::MetaNameValue;
+use syn::Path;
 use syn::Type;
+use syn::Variant;
 use syn::Visibility;
 
 /// The function that derives the actual implementation.
 #[proc_macro_attribute]
 pub fn bitfield(
-    _args: proc_macro::TokenStream,
+    args: proc_macro::TokenStream,
     input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
+    let mut bitfield_args = BitfieldArgs::default();
+    let args_parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("track_init") {
+            bitfield_args.track_init = true;
+            Ok(())
+        } else if meta.path.is_ident("eq_by_fields") {
+            bitfield_args.eq_by_fields = true;
+            Ok(())
+        } else {
+            Err(meta.error("unrecognized #[bitfield] argument"))
+        }
+    });
+    parse_macro_input!(args with args_parser);
     let derive_input = parse_macro_input!(input as DeriveInput);
 
-    let expanded = bitfield_impl(&derive_input).unwrap_or_else(|err| {
+    let expanded = bitfield_impl(&derive_input, &bitfield_args).unwrap_or_else(|err| {
         let compile_error = err.to_compile_error();
         quote! {
             #compile_error
@@ -50,17 +69,54 @@ pub fn bitfield(
     expanded.into()
 }
 
-fn bitfield_impl(ast: &DeriveInput) -> Result<TokenStream> {
+// Arguments given to the attribute, as in `#[bitfield(track_init)]`.
+#[derive(Default)]
+struct BitfieldArgs {
+    track_init: bool,
+    eq_by_fields: bool,
+}
+
+fn bitfield_impl(ast: &DeriveInput, args: &BitfieldArgs) -> Result<TokenStream> {
+    let is_named_struct = matches!(
+        &ast.data,
+        Data::Struct(DataStruct {
+            fields: Fields::Named(_),
+            ..
+        })
+    );
+
     if !ast.generics.params.is_empty() {
+        if !is_named_struct {
+            return Err(Error::new(
+                Span::call_site(),
+                "#[bitfield] does not support generic parameters",
+            ));
+        }
+        if ast.generics.params.len() != 1 || ast.generics.type_params().count() != 1 {
+            return Err(Error::new_spanned(
+                &ast.generics,
+                "#[bitfield] structs support a single type parameter only",
+            ));
+        }
+    }
+
+    if args.track_init && !is_named_struct {
+        return Err(Error::new(
+            Span::call_site(),
+            "#[bitfield(track_init)] only works with structs that have named fields",
+        ));
+    }
+
+    if args.eq_by_fields && !is_named_struct {
         return Err(Error::new(
             Span::call_site(),
-            "#[bitfield] does not support generic parameters",
+            "#[bitfield(eq_by_fields)] only works with structs that have named fields",
         ));
     }
 
     match &ast.data {
         Data::Struct(data_struct) => match &data_struct.fields {
-            Fields::Named(fields_named) => bitfield_struct_impl(ast, fields_named),
+            Fields::Named(fields_named) => bitfield_struct_impl(ast, fields_named, args),
             Fields::Unnamed(fields_unnamed) => bitfield_tuple_struct_impl(ast, fields_unnamed),
             Fields::Unit => Err(Error::new(
                 Span::call_site(),
@@ -124,6 +180,8 @@ fn bitfield_tuple_struct_impl(ast: &DeriveInput, fields: &FieldsUnnamed) -> Resu
         span => val.0 as u64
     };
 
+    // The casts carry the span of the field type so that errors point at it, which also makes
+    // them subject to the lints of the crate using the macro.
     let expanded = quote! {
         #ast
 
@@ -133,13 +191,13 @@ fn bitfield_tuple_struct_impl(ast: &DeriveInput, fields: &FieldsUnnamed) -> Resu
             type GetterType = Self;
 
             #[inline]
-            #[allow(clippy::unnecessary_cast)]
+            #[allow(clippy::unnecessary_cast, clippy::cast_possible_truncation)]
             fn from_u64(val: u64) -> Self::GetterType {
                 Self(#from_u64)
             }
 
             #[inline]
-            #[allow(clippy::unnecessary_cast)]
+            #[allow(clippy::unnecessary_cast, clippy::cast_lossless)]
             fn into_u64(val: Self::SetterType) -> u64 {
                 #into_u64
             }
@@ -152,12 +210,182 @@ fn bitfield_tuple_struct_impl(ast: &DeriveInput, fields: &FieldsUnnamed) -> Resu
 fn bitfield_enum_impl(ast: &DeriveInput, data: &DataEnum) -> Result<TokenStream> {
     let mut ast = ast.clone();
     let width = parse_remove_bits_attr(&mut ast)?;
+    if data.variants.iter().any(is_fallback_variant) {
+        return bitfield_enum_with_fallback_impl(&ast, width);
+    }
     match width {
         None => bitfield_enum_without_width_impl(&ast, data),
         Some(width) => bitfield_enum_with_width_impl(&ast, data, width),
     }
 }
 
+fn is_fallback_variant(variant: &Variant) -> bool {
+    variant
+        .attrs
+        .iter()
+        .any(|attr| attr.path().is_ident("fallback"))
+}
+
+// Expand to an impl of BitFieldSpecifier for an enum with a fallback variant like:
+//
+//     #[bitfield]
+//     #[bits = 2]
+//     #[derive(Debug, PartialEq)]
+//     enum Command {
+//         Read = 0b00,
+//         Write = 0b01,
+//         #[fallback]
+//         Unknown(u8),
+//     }
+//
+// Raw values that do not match any other variant are decoded into the fallback variant, so the
+// getter never fails. The discriminants are removed from the emitted enum because an enum with a
+// tuple variant can't have them without a repr.
+fn bitfield_enum_with_fallback_impl(ast: &DeriveInput, width: Option<u64>) -> Result<TokenStream> {
+    let width = width.ok_or_else(|| {
+        Error::new(
+            Span::call_site(),
+            "#[bitfield] enum with a #[fallback] variant must specify #[bits = N]",
+        )
+    })?;
+    if width > 64 {
+        return Err(Error::new(
+            Span::call_site(),
+            "max width of bitfield enum is 64",
+        ));
+    }
+    let bits = width as u8;
+
+    let mut ast = ast.clone();
+    let ident = ast.ident.clone();
+    let variants = match &mut ast.data {
+        Data::Enum(data) => &mut data.variants,
+        _ => unreachable!(),
+    };
+
+    let mut fallback = None;
+    let mut declare_discriminants = Vec::new();
+    let mut match_discriminants = Vec::new();
+    let mut match_variants = Vec::new();
+    for variant in variants.iter_mut() {
+        if is_fallback_variant(variant) {
+            variant
+                .attrs
+                .retain(|attr| !attr.path().is_ident("fallback"));
+            if fallback.is_some() {
+                return Err(Error::new_spanned(
+                    &variant.ident,
+                    "#[bitfield] enum may only have one #[fallback] variant",
+                ));
+            }
+            match &variant.fields {
+                Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                    fallback = Some((variant.ident.clone(), fields.unnamed[0].ty.clone()));
+                }
+                _ => {
+                    return Err(Error::new_spanned(
+                        &variant.ident,
+                        "#[fallback] variant must have exactly one unnamed field",
+                    ))
+                }
+            }
+            continue;
+        }
+
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new_spanned(
+                &variant.ident,
+                "only the #[fallback] variant of a #[bitfield] enum may have fields",
+            ));
+        }
+        let (_, value) = variant.discriminant.take().ok_or_else(|| {
+            Error::new_spanned(
+                &variant.ident,
+                "variants of a #[bitfield] enum with a #[fallback] variant must have explicit \
+                 discriminants",
+            )
+        })?;
+
+        let variant = &variant.ident;
+        let span = variant.span();
+        // Same bounds check as `get_declare_discriminants_for_enum`.
+        declare_discriminants.push(quote_spanned! {span=>
+            #[allow(non_upper_case_globals)]
+            const #variant: u64 = {
+                const IS_IN_BOUNDS: bool = ((#value) as u128) < (1u128 << #bits);
+                const ASSERT: u64 = 0 - !IS_IN_BOUNDS as u64;
+
+                (#value) as u64 + ASSERT
+            };
+        });
+        match_discriminants.push(quote! {
+            discriminant::#variant => #ident::#variant,
+        });
+        match_variants.push(quote! {
+            #ident::#variant => discriminant::#variant,
+        });
+    }
+
+    let (fallback, fallback_type) = fallback.expect("enum has a fallback variant");
+
+    // The discriminant constants are declared in each function, which the crate using the macro may
+    // never call, so they are exempt from `dead_code`.
+    let expanded = quote! {
+        #ast
+
+        impl bit_field::BitFieldSpecifier for #ident {
+            const FIELD_WIDTH: u8 = #bits;
+            type SetterType = Self;
+            type GetterType = Self;
+
+            #[inline]
+            #[allow(clippy::unnecessary_cast)]
+            fn from_u64(val: u64) -> Self::GetterType {
+                struct discriminant;
+                #[allow(dead_code)]
+                impl discriminant {
+                    #(#declare_discriminants)*
+                }
+                match val {
+                    #(#match_discriminants)*
+                    v => #ident::#fallback(v as #fallback_type),
+                }
+            }
+
+            #[inline]
+            #[allow(clippy::unnecessary_cast)]
+            fn into_u64(val: Self::SetterType) -> u64 {
+                struct discriminant;
+                #[allow(dead_code)]
+                impl discriminant {
+                    #(#declare_discriminants)*
+                }
+                match val {
+                    #(#match_variants)*
+                    #ident::#fallback(v) => v as u64,
+                }
+            }
+        }
+
+        impl #ident {
+            #[allow(clippy::unnecessary_cast)]
+            pub fn discriminant(&self) -> u64 {
+                struct discriminant;
+                #[allow(dead_code)]
+                impl discriminant {
+                    #(#declare_discriminants)*
+                }
+                match self {
+                    #(#match_variants)*
+                    #ident::#fallback(v) => *v as u64,
+                }
+            }
+        }
+    };
+
+    Ok(expanded)
+}
+
 fn bitfield_enum_with_width_impl(
     ast: &DeriveInput,
     data: &DataEnum,
@@ -171,6 +399,8 @@ fn bitfield_enum_with_width_impl(
     }
     let bits = width as u8;
     let declare_discriminants = get_declare_discriminants_for_enum(bits, ast, data);
+    let into_u64 = get_enum_into_u64(bits, ast)?;
+    let discriminant_impl = get_enum_discriminant_impl(ast, data);
 
     let ident = &ast.ident;
     let type_name = ident.to_string();
@@ -204,9 +434,16 @@ fn bitfield_enum_with_width_impl(
 
             #[inline]
             fn into_u64(val: Self::SetterType) -> u64 {
-                val as u64
+                #into_u64
+            }
+
+            #[inline]
+            fn is_valid(val: u64) -> bool {
+                Self::from_u64(val).is_ok()
             }
         }
+
+        #discriminant_impl
     };
 
     Ok(expanded)
@@ -245,6 +482,8 @@ fn bitfield_enum_without_width_impl(ast: &DeriveInput, data: &DataEnum) -> Resul
 
     let bits = len.trailing_zeros() as u8;
     let declare_discriminants = get_declare_discriminants_for_enum(bits, ast, data);
+    let into_u64 = get_enum_into_u64(bits, ast)?;
+    let discriminant_impl = get_enum_discriminant_impl(ast, data);
 
     let match_discriminants = variants.iter().map(|variant| {
         let variant = &variant.ident;
@@ -275,14 +514,72 @@ fn bitfield_enum_without_width_impl(ast: &DeriveInput, data: &DataEnum) -> Resul
 
             #[inline]
             fn into_u64(val: Self::SetterType) -> u64 {
-                val as u64
+                #into_u64
             }
         }
+
+        #discriminant_impl
     };
 
     Ok(expanded)
 }
 
+// The discriminant of a fieldless enum can be read with `as`, but that needs the value rather than
+// a reference, so also provide it through a method.
+fn get_enum_discriminant_impl(ast: &DeriveInput, data: &DataEnum) -> TokenStream {
+    let ident = &ast.ident;
+    let variants = data.variants.iter().map(|variant| &variant.ident);
+
+    quote! {
+        impl #ident {
+            pub fn discriminant(&self) -> u64 {
+                match self {
+                    #(#ident::#variants => #ident::#variants as u64,)*
+                }
+            }
+        }
+    }
+}
+
+// Converts an enum value to u64, going through the integer type of its #[repr(uN)] attribute if
+// it has one. The repr must be wide enough to hold `bits` bits.
+fn get_enum_into_u64(bits: u8, ast: &DeriveInput) -> Result<TokenStream> {
+    let repr = match parse_enum_repr(&ast.attrs)? {
+        Some(repr) => repr,
+        None => return Ok(quote!(val as u64)),
+    };
+
+    let repr_bits = match repr.to_string().as_str() {
+        "u8" => 8,
+        "u16" => 16,
+        "u32" => 32,
+        "u64" => 64,
+        _ => return Ok(quote!(val as u64)),
+    };
+    if bits > repr_bits {
+        return Err(Error::new_spanned(
+            repr,
+            format!("bitfield enum of {} bits does not fit in its repr", bits),
+        ));
+    }
+
+    Ok(quote! {
+        val as #repr as u64
+    })
+}
+
+// For example: #[repr(u16)]
+fn parse_enum_repr(attrs: &[Attribute]) -> Result<Option<Ident>> {
+    for attr in attrs {
+        if attr.path().is_ident("repr") {
+            if let Ok(repr) = attr.parse_args::<Ident>() {
+                return Ok(Some(repr));
+            }
+        }
+    }
+    Ok(None)
+}
+
 fn get_declare_discriminants_for_enum(
     bits: u8,
     ast: &DeriveInput,
@@ -336,24 +633,43 @@ fn get_declare_discriminants_for_enum(
         .collect()
 }
 
-fn bitfield_struct_impl(ast: &DeriveInput, fields: &FieldsNamed) -> Result<TokenStream> {
+fn bitfield_struct_impl(
+    ast: &DeriveInput,
+    fields: &FieldsNamed,
+    args: &BitfieldArgs,
+) -> Result<TokenStream> {
+    let track_init = args.track_init;
     let name = &ast.ident;
     let vis = &ast.vis;
     let attrs = &ast.attrs;
-    let fields = get_struct_fields(fields)?;
-    let struct_def = get_struct_def(vis, name, &fields);
-    let bits_impl = get_bits_impl(name);
-    let fields_impl = get_fields_impl(&fields);
-    let debug_fmt_impl = get_debug_fmt_impl(name, &fields);
+    let generics = &ast.generics;
+    let generic_param = generics.type_params().next().map(|param| &param.ident);
+    let fields = get_struct_fields(fields, generic_param)?;
+    let offset_checks = get_offset_checks(&fields)?;
+    check_total_expected_bits(name, &fields)?;
+    let struct_def = get_struct_def(vis, name, generics, &fields, track_init);
+    let bits_impl = get_bits_impl(name, generics, track_init);
+    let fields_impl = get_fields_impl(&fields, track_init);
+    let diff_impl = get_diff_impl(&fields);
+    let validate_impl = get_validate_impl(&fields);
+    let debug_fmt_impl = get_debug_fmt_impl(name, generics, &fields);
+    let eq_impl = args
+        .eq_by_fields
+        .then(|| get_eq_by_fields_impl(name, generics, &fields));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     let expanded = quote! {
         #(#attrs)*
         #struct_def
+        #(#offset_checks)*
         #bits_impl
-        impl #name {
+        impl #impl_generics #name #ty_generics #where_clause {
             #(#fields_impl)*
+            #diff_impl
+            #validate_impl
         }
         #debug_fmt_impl
+        #eq_impl
     };
 
     Ok(expanded)
@@ -363,11 +679,26 @@ struct FieldSpec<'a> {
     ident: &'a Ident,
     ty: &'a Type,
     expected_bits: Option<LitInt>,
+    // Whether the field's type is the struct's type parameter.
+    is_generic: bool,
+    // Whether the field has a #[mbz] attribute, i.e. is reserved and must be zero.
+    mbz: bool,
+    // Attributes given with #[accessor_attr(...)], put on the getter and setter.
+    accessor_attrs: Vec<Meta>,
+    // Bit offset given with #[offset = N]. Fields without one follow the previous field.
+    offset: Option<LitInt>,
+    // Functions given with #[transform(get = "...", set = "...")], applied to the value returned
+    // by the getter and to the value passed to the setter.
+    get_transform: Option<Path>,
+    set_transform: Option<Path>,
 }
 
 // Unwrap ast to get the named fields. We only care about field names and types:
 // "myfield : BitField3" -> ("myfield", Token(BitField3))
-fn get_struct_fields(fields: &FieldsNamed) -> Result<Vec<FieldSpec>> {
+fn get_struct_fields<'a>(
+    fields: &'a FieldsNamed,
+    generic_param: Option<&Ident>,
+) -> Result<Vec<FieldSpec<'a>>> {
     let mut vec = Vec::new();
 
     for field in &fields.named {
@@ -376,39 +707,212 @@ fn get_struct_fields(fields: &FieldsNamed) -> Result<Vec<FieldSpec>> {
             .as_ref()
             .expect("Fields::Named has named fields");
         let ty = &field.ty;
-        let expected_bits = parse_bits_attr(&field.attrs)?;
+        let FieldAttrs {
+            expected_bits,
+            mbz,
+            accessor_attrs,
+            offset,
+            get_transform,
+            set_transform,
+        } = parse_field_attrs(&field.attrs)?;
+        let is_generic = generic_param.is_some_and(|param| {
+            matches!(ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident(param))
+        });
+        // The width of a type parameter can't be used in the array length of the struct, so it
+        // has to be given up front.
+        if is_generic && expected_bits.is_none() {
+            return Err(Error::new_spanned(
+                field,
+                "fields of generic type need a #[bits = N] attribute",
+            ));
+        }
+        if is_zero_width(ty) {
+            return Err(Error::new_spanned(
+                ty,
+                "zero-width field: B0 and BitField0 take no bits and cannot be used as field types",
+            ));
+        }
         vec.push(FieldSpec {
             ident,
             ty,
             expected_bits,
+            is_generic,
+            mbz,
+            accessor_attrs,
+            offset,
+            get_transform,
+            set_transform,
         });
     }
 
     Ok(vec)
 }
 
-// For example: #[bits = 1]
-fn parse_bits_attr(attrs: &[Attribute]) -> Result<Option<LitInt>> {
-    let mut expected_bits = None;
+// Whether the type is one of the zero-width specifiers, B0 or BitField0. A field of such a type is
+// almost always a mistake, and would silently take no bits.
+fn is_zero_width(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "B0" || segment.ident == "BitField0"),
+        _ => false,
+    }
+}
+
+// Returns the width of a field for use in constant expressions such as the size of the struct.
+// A generic field's #[bits = N] attribute stands in for its width, which is checked against the
+// real width in its getter and setter.
+fn field_width_const(spec: &FieldSpec) -> TokenStream {
+    match &spec.expected_bits {
+        Some(bits) if spec.is_generic => quote!(#bits),
+        _ => {
+            let ty = spec.ty;
+            quote!(<#ty as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize)
+        }
+    }
+}
+
+// Checks that fields with an #[offset = N] attribute start after the end of the previous field.
+// This is done here when the widths of the previous fields are known, for a better error message,
+// and otherwise by the returned constant assertions.
+fn get_offset_checks(fields: &[FieldSpec]) -> Result<Vec<TokenStream>> {
+    let mut checks = Vec::new();
+    // Terms adding up to the end of the previous field, and its value if known.
+    let mut end_terms = Vec::new();
+    let mut known_end = Some(0);
+
+    for spec in fields {
+        if let Some(offset) = &spec.offset {
+            let offset_val: u64 = offset.base10_parse()?;
+            match known_end {
+                Some(end) if end > offset_val => {
+                    return Err(Error::new_spanned(
+                        offset,
+                        format!(
+                            "field `{}` at bit {} overlaps the previous fields, which end at bit {}",
+                            spec.ident, offset_val, end
+                        ),
+                    ));
+                }
+                Some(_) => {}
+                None => {
+                    let msg = format!("field `{}` overlaps the previous fields", spec.ident);
+                    checks.push(quote_spanned! {offset.span()=>
+                        const _: () = assert!(#(#end_terms)+* <= #offset, #msg);
+                    });
+                }
+            }
+            end_terms = vec![quote!(#offset)];
+            known_end = Some(offset_val);
+        }
+        end_terms.push(field_width_const(spec));
+        known_end = known_end
+            .zip(field_width_before_expansion(spec))
+            .map(|(end, width)| end + width);
+    }
+
+    Ok(checks)
+}
+
+// If every field has a #[bits = N] attribute, the struct size is known up front, so a mistyped
+// attribute that makes the struct not a whole number of bytes can be reported with the totals
+// involved rather than only through the per-field checks.
+fn check_total_expected_bits(name: &Ident, fields: &[FieldSpec]) -> Result<()> {
+    let mut total: u64 = 0;
+    for spec in fields {
+        if let Some(offset) = &spec.offset {
+            total = offset.base10_parse()?;
+        }
+        match &spec.expected_bits {
+            Some(bits) => total += bits.base10_parse::<u64>()?,
+            None => return Ok(()),
+        }
+    }
+
+    if total % 8 != 0 {
+        let below = total / 8 * 8;
+        return Err(Error::new(
+            name.span(),
+            format!(
+                "#[bits] attributes of the fields add up to {} bits, expected a multiple of 8 \
+                 such as {} or {}",
+                total,
+                below,
+                below + 8
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+    expected_bits: Option<LitInt>,
+    mbz: bool,
+    accessor_attrs: Vec<Meta>,
+    offset: Option<LitInt>,
+    get_transform: Option<Path>,
+    set_transform: Option<Path>,
+}
+
+// Parses the attributes of a struct field, for example: #[bits = 1], #[mbz], #[offset = 12],
+// #[accessor_attr(must_use)] or #[transform(get = "decode", set = "encode")].
+fn parse_field_attrs(attrs: &[Attribute]) -> Result<FieldAttrs> {
+    let mut field_attrs = FieldAttrs::default();
 
     for attr in attrs {
         if attr.path().is_ident("doc") {
             continue;
         }
         if let Some(v) = try_parse_bits_attr(attr) {
-            expected_bits = Some(v);
+            field_attrs.expected_bits = Some(v);
+            continue;
+        }
+        if let Some(v) = try_parse_int_attr(attr, "offset") {
+            field_attrs.offset = Some(v);
+            continue;
+        }
+        if attr.path().is_ident("mbz") {
+            attr.meta.require_path_only()?;
+            field_attrs.mbz = true;
+            continue;
+        }
+        if attr.path().is_ident("accessor_attr") {
+            field_attrs.accessor_attrs.push(attr.parse_args()?);
+            continue;
+        }
+        if attr.path().is_ident("transform") {
+            attr.parse_nested_meta(|meta| {
+                let transform = if meta.path.is_ident("get") {
+                    &mut field_attrs.get_transform
+                } else if meta.path.is_ident("set") {
+                    &mut field_attrs.set_transform
+                } else {
+                    return Err(meta.error("expected `get` or `set`"));
+                };
+                *transform = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                Ok(())
+            })?;
             continue;
         }
 
         return Err(Error::new_spanned(attr, "unrecognized attribute"));
     }
 
-    Ok(expected_bits)
+    Ok(field_attrs)
 }
 
 // This function will return None if the attribute is not #[bits = *].
 fn try_parse_bits_attr(attr: &Attribute) -> Option<LitInt> {
-    if attr.path().is_ident("bits") {
+    try_parse_int_attr(attr, "bits")
+}
+
+// Returns the value of a #[name = N] attribute, or None if the attribute is something else.
+fn try_parse_int_attr(attr: &Attribute, name: &str) -> Option<LitInt> {
+    if attr.path().is_ident(name) {
         if let Meta::NameValue(MetaNameValue {
             value:
                 syn::Expr::Lit(syn::ExprLit {
@@ -441,49 +945,208 @@ fn parse_remove_bits_attr(ast: &mut DeriveInput) -> Result<Option<u64>> {
     Ok(width)
 }
 
-fn get_struct_def(vis: &Visibility, name: &Ident, fields: &[FieldSpec]) -> TokenStream {
-    let mut field_types = Vec::new();
+fn get_struct_def(
+    vis: &Visibility,
+    name: &Ident,
+    generics: &Generics,
+    fields: &[FieldSpec],
+    track_init: bool,
+) -> TokenStream {
+    // The fields after the last #[offset = N] attribute add up to the size of the struct, starting
+    // from N.
+    let mut field_widths = Vec::new();
     for spec in fields {
-        field_types.push(spec.ty);
+        if let Some(offset) = &spec.offset {
+            field_widths = vec![quote!(#offset)];
+        }
+        field_widths.push(field_width_const(spec));
     }
 
     // `(BitField1::FIELD_WIDTH + BitField3::FIELD_WIDTH + ...)`
     let data_size_in_bits = quote! {
         (
-            #(
-                <#field_types as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
-            )+*
+            #(#field_widths)+*
         )
     };
 
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    // The type parameter is otherwise only used by the accessors.
+    let marker_def = generics.type_params().next().map(|param| {
+        let param = &param.ident;
+        quote! {
+            _marker: ::std::marker::PhantomData<#param>,
+        }
+    });
+    let marker_init = marker_def.as_ref().map(|_| {
+        quote! {
+            _marker: ::std::marker::PhantomData,
+        }
+    });
+
+    // With track_init, debug builds carry one flag per data byte recording whether it has been
+    // written since new().
+    let written_def = track_init.then(|| {
+        quote! {
+            #[cfg(debug_assertions)]
+            written: [bool; #data_size_in_bits / 8],
+        }
+    });
+    let written_init = |val: bool| {
+        track_init.then(|| {
+            quote! {
+                #[cfg(debug_assertions)]
+                written: [#val; #data_size_in_bits / 8],
+            }
+        })
+    };
+    let written_new = written_init(false);
+    let written_all = written_init(true);
+
+    // Checks run by `TryFrom`, which only accepts values that every field can decode.
+    let field_checks = fields.iter().map(|spec| {
+        let ty = spec.ty;
+        let field_name = spec.ident.to_string();
+        let raw_getter_ident = Ident::new(&format!("get_{}_raw", spec.ident), Span::call_site());
+        quote! {
+            let val = value.#raw_getter_ident();
+            if !<#ty as ::bit_field::BitFieldSpecifier>::is_valid(val) {
+                return Err(::bit_field::InvalidFieldValue::new(#field_name, val));
+            }
+        }
+    });
+
     quote! {
         #[repr(C)]
-        #vis struct #name {
+        #vis struct #name #generics #where_clause {
             data: [u8; #data_size_in_bits / 8],
+            #written_def
+            #marker_def
         }
 
-        impl #name {
-            pub fn new() -> #name {
-                let _: ::bit_field::Check<[u8; #data_size_in_bits % 8]>;
+        impl #impl_generics #name #ty_generics #where_clause {
+            pub const TOTAL_BITS: usize = #data_size_in_bits;
+            pub const TOTAL_BYTES: usize = #data_size_in_bits / 8;
+
+            pub fn new() -> #name #ty_generics {
+                const _: () = ::bit_field::check_total_bits(#data_size_in_bits);
 
                 #name {
                     data: [0; #data_size_in_bits / 8],
+                    #written_new
+                    #marker_init
+                }
+            }
+
+            pub fn as_bytes(&self) -> &[u8] {
+                &self.data
+            }
+
+            pub fn to_le<T>(&self) -> T
+            where
+                T: ::bit_field::BitFieldPrimitive<Bytes = [u8; #data_size_in_bits / 8]>,
+            {
+                T::from_le_bytes(self.data)
+            }
+
+            pub fn to_be<T>(&self) -> T
+            where
+                T: ::bit_field::BitFieldPrimitive<Bytes = [u8; #data_size_in_bits / 8]>,
+            {
+                T::from_be_bytes(self.data)
+            }
+
+            pub fn from_le<T>(val: T) -> #name #ty_generics
+            where
+                T: ::bit_field::BitFieldPrimitive<Bytes = [u8; #data_size_in_bits / 8]>,
+            {
+                #name {
+                    data: val.to_le_bytes(),
+                    #written_all
+                    #marker_init
+                }
+            }
+
+            pub fn from_be<T>(val: T) -> #name #ty_generics
+            where
+                T: ::bit_field::BitFieldPrimitive<Bytes = [u8; #data_size_in_bits / 8]>,
+            {
+                #name {
+                    data: val.to_be_bytes(),
+                    #written_all
+                    #marker_init
+                }
+            }
+
+            pub fn from_bytes(bytes: [u8; #data_size_in_bits / 8]) -> #name #ty_generics {
+                #name {
+                    data: bytes,
+                    #written_all
+                    #marker_init
+                }
+            }
+
+            pub fn to_le_u64_array(&self) -> [u64; (#data_size_in_bits / 8).div_ceil(8)] {
+                let mut words = [0; (#data_size_in_bits / 8).div_ceil(8)];
+                for (word, chunk) in words.iter_mut().zip(self.data.chunks(8)) {
+                    let mut bytes = [0; 8];
+                    bytes[..chunk.len()].copy_from_slice(chunk);
+                    *word = u64::from_le_bytes(bytes);
                 }
+                words
+            }
+
+            pub fn from_le_u64_array(words: [u64; (#data_size_in_bits / 8).div_ceil(8)]) -> #name #ty_generics {
+                let mut bytes = [0; #data_size_in_bits / 8];
+                for (chunk, word) in bytes.chunks_mut(8).zip(words) {
+                    let len = chunk.len();
+                    chunk.copy_from_slice(&word.to_le_bytes()[..len]);
+                }
+                Self::from_bytes(bytes)
+            }
+        }
+
+        impl #impl_generics ::std::convert::TryFrom<[u8; #data_size_in_bits / 8]> for #name #ty_generics #where_clause {
+            type Error = ::bit_field::InvalidFieldValue;
+
+            fn try_from(bytes: [u8; #data_size_in_bits / 8]) -> ::std::result::Result<Self, Self::Error> {
+                let value = Self::from_bytes(bytes);
+                #(#field_checks)*
+                Ok(value)
             }
         }
     }
 }
 
 // Implement setter and getter for all fields.
-fn get_fields_impl(fields: &[FieldSpec]) -> Vec<TokenStream> {
+fn get_fields_impl(fields: &[FieldSpec], track_init: bool) -> Vec<TokenStream> {
     let mut impls = Vec::new();
-    // This vec keeps track of types before this field, used to generate the offset.
-    let current_types = &mut vec![quote!(::bit_field::BitField0)];
+    // This vec keeps track of the widths of the fields before this one, or of those since the last
+    // #[offset = N] attribute along with N, used to generate the offset.
+    let mut offset_terms = vec![quote!(
+        <::bit_field::BitField0 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+    )];
+    // Offset of this field if it is known while expanding the macro.
+    let mut known_offset = Some(0);
 
     for spec in fields {
         let ty = spec.ty;
+        if let Some(offset) = &spec.offset {
+            offset_terms = vec![quote!(#offset)];
+            known_offset = offset.base10_parse().ok();
+        }
         let getter_ident = Ident::new(format!("get_{}", spec.ident).as_str(), Span::call_site());
         let setter_ident = Ident::new(format!("set_{}", spec.ident).as_str(), Span::call_site());
+        let raw_getter_ident = Ident::new(
+            format!("get_{}_raw", spec.ident).as_str(),
+            Span::call_site(),
+        );
+        let swap_ident = Ident::new(format!("swap_{}", spec.ident).as_str(), Span::call_site());
+        let getter_attrs = &spec.accessor_attrs;
+        // The setter returns nothing, so `must_use` would only flag every call to it.
+        let setter_attrs = spec
+            .accessor_attrs
+            .iter()
+            .filter(|meta| !meta.path().is_ident("must_use"));
 
         // Optional #[bits = N] attribute to provide compile-time checked
         // documentation of how many bits some field covers.
@@ -492,37 +1155,243 @@ fn get_fields_impl(fields: &[FieldSpec]) -> Vec<TokenStream> {
             // bit field specifier, this will fail to compile with an error
             // pointing into the #[bits = N] attribute.
             let span = expected_bits.span();
-            quote_spanned! {span=>
-                #[allow(dead_code)]
-                const EXPECTED_BITS: [(); #expected_bits] =
-                    [(); <#ty as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize];
+            if spec.is_generic {
+                // Items inside the getter can't use the type parameter, so the check is an
+                // associated const, evaluated once the struct is used with a concrete type.
+                let check_ident = Ident::new(
+                    &format!("{}_EXPECTED_BITS", spec.ident.to_string().to_uppercase()),
+                    span,
+                );
+                impls.push(quote_spanned! {span=>
+                    #[allow(non_upper_case_globals)]
+                    const #check_ident: () = assert!(
+                        <#ty as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                            == #expected_bits,
+                        "#[bits] attribute does not match the width of the field type"
+                    );
+                });
+                quote_spanned! {span=>
+                    #[allow(clippy::let_unit_value)]
+                    let () = Self::#check_ident;
+                }
+            } else {
+                quote_spanned! {span=>
+                    #[allow(dead_code)]
+                    const EXPECTED_BITS: [(); #expected_bits] =
+                        [(); <#ty as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize];
+                }
+            }
+        });
+
+        let from_u64 = quote!(<#ty as ::bit_field::BitFieldSpecifier>::from_u64(val));
+        let getter_val = match &spec.get_transform {
+            Some(decode) => quote!(#decode(#from_u64)),
+            None => from_u64,
+        };
+        let setter_val = match &spec.set_transform {
+            Some(encode) => quote!(#encode(val)),
+            None => quote!(val),
+        };
+
+        let field_name = spec.ident.to_string();
+        let check_written = track_init.then(|| {
+            quote! {
+                #[cfg(debug_assertions)]
+                self.check_written(offset, <#ty as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH, #field_name);
             }
         });
 
         impls.push(quote! {
+            #(#[#getter_attrs])*
             pub fn #getter_ident(&self) -> <#ty as ::bit_field::BitFieldSpecifier>::GetterType {
                 #check_expected_bits
-                let offset = #(<#current_types as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize)+*;
+                let offset = #(#offset_terms)+*;
+                #check_written
                 let val = self.get(offset, <#ty as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH);
-                <#ty as ::bit_field::BitFieldSpecifier>::from_u64(val)
+                #getter_val
             }
 
+            #(#[#setter_attrs])*
             pub fn #setter_ident(&mut self, val: <#ty as ::bit_field::BitFieldSpecifier>::SetterType) {
-                let val = <#ty as ::bit_field::BitFieldSpecifier>::into_u64(val);
+                #check_expected_bits
+                let val = <#ty as ::bit_field::BitFieldSpecifier>::into_u64(#setter_val);
                 debug_assert!(val <= ::bit_field::max::<#ty>());
-                let offset = #(<#current_types as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize)+*;
+                let offset = #(#offset_terms)+*;
                 self.set(offset, <#ty as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH, val)
             }
+
+            pub fn #raw_getter_ident(&self) -> u64 {
+                let offset = #(#offset_terms)+*;
+                #check_written
+                self.get(offset, <#ty as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH)
+            }
+
+            pub fn #swap_ident(&mut self, val: <#ty as ::bit_field::BitFieldSpecifier>::SetterType) -> <#ty as ::bit_field::BitFieldSpecifier>::GetterType {
+                let old = self.#getter_ident();
+                self.#setter_ident(val);
+                old
+            }
         });
 
-        current_types.push(quote!(#ty));
+        // Byte-aligned fields can also be borrowed straight from the data array, or copied into it.
+        // This requires knowing the layout here rather than through FIELD_WIDTH at compile time.
+        let known_width = field_width_before_expansion(spec);
+        if let (Some(offset), Some(width)) = (known_offset, known_width) {
+            if offset % 8 == 0 && width % 8 == 0 {
+                let bytes_getter_ident = Ident::new(
+                    format!("get_{}_bytes", spec.ident).as_str(),
+                    Span::call_site(),
+                );
+                let bytes_setter_ident = Ident::new(
+                    format!("set_{}_bytes", spec.ident).as_str(),
+                    Span::call_site(),
+                );
+                let start = (offset / 8) as usize;
+                let end = ((offset + width) / 8) as usize;
+                let len = end - start;
+                let check_written = track_init.then(|| {
+                    let width = width as u8;
+                    quote! {
+                        #[cfg(debug_assertions)]
+                        self.check_written(#offset as usize, #width, #field_name);
+                    }
+                });
+                let mark_written = track_init.then(|| {
+                    quote! {
+                        #[cfg(debug_assertions)]
+                        self.written[#start..#end].fill(true);
+                    }
+                });
+                impls.push(quote! {
+                    pub fn #bytes_getter_ident(&self) -> &[u8] {
+                        #check_written
+                        &self.data[#start..#end]
+                    }
+
+                    pub fn #bytes_setter_ident(&mut self, bytes: &[u8]) {
+                        assert_eq!(bytes.len(), #len, "field `{}` is {} bytes long", #field_name, #len);
+                        #mark_written
+                        self.data[#start..#end].copy_from_slice(bytes);
+                    }
+                });
+            }
+        }
+        known_offset = known_offset.zip(known_width).map(|(o, w)| o + w);
+
+        offset_terms.push(quote!(<#ty as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize));
     }
 
     impls
 }
 
+// Returns the width of a field when it can be told without the compiler: for the built-in
+// specifiers `bool`, `BN` and `BitFieldN`, or when the field has a #[bits = N] attribute, which is
+// checked against the real width.
+fn field_width_before_expansion(spec: &FieldSpec) -> Option<u64> {
+    if let Some(bits) = &spec.expected_bits {
+        return bits.base10_parse().ok();
+    }
+    let ident = match spec.ty {
+        Type::Path(path) if path.qself.is_none() => path.path.get_ident()?.to_string(),
+        _ => return None,
+    };
+    if ident == "bool" {
+        return Some(1);
+    }
+    let width = ident
+        .strip_prefix("BitField")
+        .or_else(|| ident.strip_prefix('B'))?
+        .parse()
+        .ok()?;
+    if (1..=64).contains(&width) {
+        Some(width)
+    } else {
+        None
+    }
+}
+
+// Implement `diff`, which compares the raw values of all fields.
+fn get_diff_impl(fields: &[FieldSpec]) -> TokenStream {
+    let mut checks = Vec::new();
+    for spec in fields {
+        let field_name = spec.ident.to_string();
+        let raw_getter_ident = Ident::new(&format!("get_{}_raw", spec.ident), Span::call_site());
+        checks.push(quote! {
+            let (ours, theirs) = (self.#raw_getter_ident(), other.#raw_getter_ident());
+            if ours != theirs {
+                diffs.push((#field_name, ours, theirs));
+            }
+        });
+    }
+
+    quote! {
+        pub fn diff(&self, other: &Self) -> Vec<(&'static str, u64, u64)> {
+            let mut diffs = Vec::new();
+            #(#checks)*
+            diffs
+        }
+    }
+}
+
+// Implement `validate`, which checks that all #[mbz] fields are zero. Structs without such fields
+// don't get one.
+fn get_validate_impl(fields: &[FieldSpec]) -> Option<TokenStream> {
+    let checks: Vec<TokenStream> = fields
+        .iter()
+        .filter(|spec| spec.mbz)
+        .map(|spec| {
+            let field_name = spec.ident.to_string();
+            let raw_getter_ident =
+                Ident::new(&format!("get_{}_raw", spec.ident), Span::call_site());
+            quote! {
+                let val = self.#raw_getter_ident();
+                if val != 0 {
+                    return Err(::bit_field::MbzViolation::new(#field_name, val));
+                }
+            }
+        })
+        .collect();
+    if checks.is_empty() {
+        return None;
+    }
+
+    Some(quote! {
+        pub fn validate(&self) -> ::std::result::Result<(), ::bit_field::MbzViolation> {
+            #(#checks)*
+            Ok(())
+        }
+    })
+}
+
+// Implement `PartialEq`, `Eq` and `Hash` in terms of the raw values of the fields, leaving out
+// #[mbz] fields and the bits that aren't part of any field, so that equal values hash equally.
+fn get_eq_by_fields_impl(name: &Ident, generics: &Generics, fields: &[FieldSpec]) -> TokenStream {
+    let raw_getters: Vec<Ident> = fields
+        .iter()
+        .filter(|spec| !spec.mbz)
+        .map(|spec| Ident::new(&format!("get_{}_raw", spec.ident), Span::call_site()))
+        .collect();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::std::cmp::PartialEq for #name #ty_generics #where_clause {
+            fn eq(&self, other: &Self) -> bool {
+                true #(&& self.#raw_getters() == other.#raw_getters())*
+            }
+        }
+
+        impl #impl_generics ::std::cmp::Eq for #name #ty_generics #where_clause {}
+
+        impl #impl_generics ::std::hash::Hash for #name #ty_generics #where_clause {
+            fn hash<H: ::std::hash::Hasher>(&self, state: &mut H) {
+                #(::std::hash::Hash::hash(&self.#raw_getters(), state);)*
+            }
+        }
+    }
+}
+
 // Implement setter and getter for all fields.
-fn get_debug_fmt_impl(name: &Ident, fields: &[FieldSpec]) -> TokenStream {
+fn get_debug_fmt_impl(name: &Ident, generics: &Generics, fields: &[FieldSpec]) -> TokenStream {
     // print fields:
     let mut impls = Vec::new();
     for spec in fields {
@@ -533,9 +1402,19 @@ fn get_debug_fmt_impl(name: &Ident, fields: &[FieldSpec]) -> TokenStream {
         });
     }
 
+    // Generic fields are printed through the getter type of whatever type they're given.
+    let mut generics = generics.clone();
+    for spec in fields.iter().filter(|spec| spec.is_generic) {
+        let ty = spec.ty;
+        generics.make_where_clause().predicates.push(parse_quote! {
+            <#ty as ::bit_field::BitFieldSpecifier>::GetterType: std::fmt::Debug
+        });
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
     let name_str = format!("{}", name);
     quote! {
-        impl std::fmt::Debug for #name {
+        impl #impl_generics std::fmt::Debug for #name #ty_generics #where_clause {
             fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
                 f.debug_struct(#name_str)
                 #(#impls)*
@@ -545,9 +1424,33 @@ fn get_debug_fmt_impl(name: &Ident, fields: &[FieldSpec]) -> TokenStream {
     }
 }
 
-fn get_bits_impl(name: &Ident) -> TokenStream {
+fn get_bits_impl(name: &Ident, generics: &Generics, track_init: bool) -> TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mark_written = track_init.then(|| {
+        quote! {
+            #[cfg(debug_assertions)]
+            {
+                self.written[byte_index] = true;
+            }
+        }
+    });
+    let check_written_impl = track_init.then(|| {
+        quote! {
+            #[cfg(debug_assertions)]
+            fn check_written(&self, offset: usize, width: u8, field: &str) {
+                let first = offset / 8;
+                let end = (offset + (width as usize)).div_ceil(8);
+                debug_assert!(
+                    self.written[first..end].iter().all(|w| *w),
+                    "bitfield field `{}` read before it was written",
+                    field
+                );
+            }
+        }
+    });
+
     quote! {
-        impl #name {
+        impl #impl_generics #name #ty_generics #where_clause {
             #[inline]
             fn check_access(&self, offset: usize, width: u8) {
                 debug_assert!(width <= 64);
@@ -555,6 +1458,8 @@ fn get_bits_impl(name: &Ident) -> TokenStream {
                 debug_assert!((offset + (width as usize)) <= (self.data.len() * 8));
             }
 
+            #check_written_impl
+
             #[inline]
             pub fn get_bit(&self, offset: usize) -> bool {
                 self.check_access(offset, 1);
@@ -575,6 +1480,7 @@ fn get_bits_impl(name: &Ident) -> TokenStream {
                 let byte_index = offset / 8;
                 let bit_offset = offset % 8;
 
+                #mark_written
                 let byte = &mut self.data[byte_index];
                 let mask = 1 << bit_offset;
 
@@ -610,6 +1516,42 @@ fn get_bits_impl(name: &Ident) -> TokenStream {
                 }
             }
         }
+
+        impl #impl_generics ::bit_field::BitFieldAccess for #name #ty_generics #where_clause {
+            #[inline]
+            fn get(&self, offset: usize, width: u8) -> u64 {
+                #name::get(self, offset, width)
+            }
+
+            #[inline]
+            fn set(&mut self, offset: usize, width: u8, val: u64) {
+                #name::set(self, offset, width, val)
+            }
+        }
+
+        impl #impl_generics ::bit_field::Bitfield for #name #ty_generics #where_clause {
+            const TOTAL_BYTES: usize = Self::TOTAL_BYTES;
+
+            #[inline]
+            fn bits() -> usize {
+                Self::TOTAL_BITS
+            }
+
+            #[inline]
+            fn as_bytes(&self) -> &[u8] {
+                #name::as_bytes(self)
+            }
+
+            #[inline]
+            fn get_bit(&self, offset: usize) -> bool {
+                #name::get_bit(self, offset)
+            }
+
+            #[inline]
+            fn set_bit(&mut self, offset: usize, val: bool) {
+                #name::set_bit(self, offset, val)
+            }
+        }
     }
 }
 
@@ -666,6 +1608,41 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn total_expected_bits_mismatch() {
+        let input: DeriveInput = parse_quote! {
+            struct MyBitField {
+                #[bits = 3]
+                a: BitField3,
+                #[bits = 6]
+                b: BitField5,
+            }
+        };
+
+        let err = bitfield_impl(&input, &BitfieldArgs::default()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "#[bits] attributes of the fields add up to 9 bits, expected a multiple of 8 such as \
+             8 or 16"
+        );
+    }
+
+    #[test]
+    fn zero_width_field() {
+        let input: DeriveInput = parse_quote! {
+            struct MyBitField {
+                a: B8,
+                b: ::bit_field::B0,
+            }
+        };
+
+        let err = bitfield_impl(&input, &BitfieldArgs::default()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "zero-width field: B0 and BitField0 take no bits and cannot be used as field types"
+        );
+    }
+
     #[test]
     fn end_to_end() {
         let input: DeriveInput = parse_quote! {
@@ -687,14 +1664,19 @@ mod tests {
                     / 8],
             }
             impl MyBitField {
+                pub const TOTAL_BITS: usize = (<BitField1 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                    + <BitField2 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                    + <BitField5 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize);
+                pub const TOTAL_BYTES: usize = (<BitField1 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                    + <BitField2 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                    + <BitField5 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize)
+                    / 8;
                 pub fn new() -> MyBitField {
-                    let _: ::bit_field::Check<[
-                        u8;
+                    const _: () = ::bit_field::check_total_bits(
                         (<BitField1 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
-                                + <BitField2 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
-                                + <BitField5 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize)
-                            % 8
-                    ]>;
+                            + <BitField2 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                            + <BitField5 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize)
+                    );
 
                     MyBitField {
                         data: [0; (<BitField1 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
@@ -703,6 +1685,111 @@ mod tests {
                             / 8],
                     }
                 }
+                pub fn as_bytes(&self) -> &[u8] {
+                    &self.data
+                }
+                pub fn to_le<T>(&self) -> T
+                where
+                    T: ::bit_field::BitFieldPrimitive<Bytes = [u8; (<BitField1 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                                + <BitField2 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                                + <BitField5 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize)
+                            / 8]>,
+                {
+                    T::from_le_bytes(self.data)
+                }
+                pub fn to_be<T>(&self) -> T
+                where
+                    T: ::bit_field::BitFieldPrimitive<Bytes = [u8; (<BitField1 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                                + <BitField2 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                                + <BitField5 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize)
+                            / 8]>,
+                {
+                    T::from_be_bytes(self.data)
+                }
+                pub fn from_le<T>(val: T) -> MyBitField
+                where
+                    T: ::bit_field::BitFieldPrimitive<Bytes = [u8; (<BitField1 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                                + <BitField2 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                                + <BitField5 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize)
+                            / 8]>,
+                {
+                    MyBitField {
+                        data: val.to_le_bytes(),
+                    }
+                }
+                pub fn from_be<T>(val: T) -> MyBitField
+                where
+                    T: ::bit_field::BitFieldPrimitive<Bytes = [u8; (<BitField1 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                                + <BitField2 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                                + <BitField5 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize)
+                            / 8]>,
+                {
+                    MyBitField {
+                        data: val.to_be_bytes(),
+                    }
+                }
+
+                pub fn from_bytes(bytes: [u8; (<BitField1 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                        + <BitField2 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                        + <BitField5 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize)
+                    / 8]) -> MyBitField {
+                    MyBitField {
+                        data: bytes,
+                    }
+                }
+
+                pub fn to_le_u64_array(&self) -> [u64; ((<BitField1 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                        + <BitField2 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                        + <BitField5 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize) / 8).div_ceil(8)] {
+                    let mut words = [0; ((<BitField1 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                        + <BitField2 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                        + <BitField5 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize) / 8).div_ceil(8)];
+                    for (word, chunk) in words.iter_mut().zip(self.data.chunks(8)) {
+                        let mut bytes = [0; 8];
+                        bytes[..chunk.len()].copy_from_slice(chunk);
+                        *word = u64::from_le_bytes(bytes);
+                    }
+                    words
+                }
+
+                pub fn from_le_u64_array(words: [u64; ((<BitField1 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                        + <BitField2 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                        + <BitField5 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize) / 8).div_ceil(8)]) -> MyBitField {
+                    let mut bytes = [0; (<BitField1 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                        + <BitField2 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                        + <BitField5 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize) / 8];
+                    for (chunk, word) in bytes.chunks_mut(8).zip(words) {
+                        let len = chunk.len();
+                        chunk.copy_from_slice(&word.to_le_bytes()[..len]);
+                    }
+                    Self::from_bytes(bytes)
+                }
+            }
+            impl ::std::convert::TryFrom<[u8; (<BitField1 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                        + <BitField2 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                        + <BitField5 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize)
+                    / 8]> for MyBitField {
+                type Error = ::bit_field::InvalidFieldValue;
+
+                fn try_from(bytes: [u8; (<BitField1 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                        + <BitField2 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                        + <BitField5 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize)
+                    / 8]) -> ::std::result::Result<Self, Self::Error> {
+                    let value = Self::from_bytes(bytes);
+                    let val = value.get_a_raw();
+                    if !<BitField1 as ::bit_field::BitFieldSpecifier>::is_valid(val) {
+                        return Err(::bit_field::InvalidFieldValue::new("a", val));
+                    }
+                    let val = value.get_b_raw();
+                    if !<BitField2 as ::bit_field::BitFieldSpecifier>::is_valid(val) {
+                        return Err(::bit_field::InvalidFieldValue::new("b", val));
+                    }
+                    let val = value.get_c_raw();
+                    if !<BitField5 as ::bit_field::BitFieldSpecifier>::is_valid(val) {
+                        return Err(::bit_field::InvalidFieldValue::new("c", val));
+                    }
+                    Ok(value)
+                }
             }
             impl MyBitField {
                 #[inline]
@@ -754,6 +1841,36 @@ mod tests {
                     }
                 }
             }
+            impl ::bit_field::BitFieldAccess for MyBitField {
+                #[inline]
+                fn get(&self, offset: usize, width: u8) -> u64 {
+                    MyBitField::get(self, offset, width)
+                }
+                #[inline]
+                fn set(&mut self, offset: usize, width: u8, val: u64) {
+                    MyBitField::set(self, offset, width, val)
+                }
+            }
+            impl ::bit_field::Bitfield for MyBitField {
+                const TOTAL_BYTES: usize = Self::TOTAL_BYTES;
+
+                #[inline]
+                fn bits() -> usize {
+                    Self::TOTAL_BITS
+                }
+                #[inline]
+                fn as_bytes(&self) -> &[u8] {
+                    MyBitField::as_bytes(self)
+                }
+                #[inline]
+                fn get_bit(&self, offset: usize) -> bool {
+                    MyBitField::get_bit(self, offset)
+                }
+                #[inline]
+                fn set_bit(&mut self, offset: usize, val: bool) {
+                    MyBitField::set_bit(self, offset, val)
+                }
+            }
             impl MyBitField {
                 pub fn get_a(&self) -> <BitField1 as ::bit_field::BitFieldSpecifier>::GetterType {
                     let offset = <::bit_field::BitField0 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize;
@@ -766,6 +1883,15 @@ mod tests {
                     let offset = <::bit_field::BitField0 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize;
                     self.set(offset, <BitField1 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH, val)
                 }
+                pub fn get_a_raw(&self) -> u64 {
+                    let offset = <::bit_field::BitField0 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize;
+                    self.get(offset, <BitField1 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH)
+                }
+                pub fn swap_a(&mut self, val: <BitField1 as ::bit_field::BitFieldSpecifier>::SetterType) -> <BitField1 as ::bit_field::BitFieldSpecifier>::GetterType {
+                    let old = self.get_a();
+                    self.set_a(val);
+                    old
+                }
                 pub fn get_b(&self) -> <BitField2 as ::bit_field::BitFieldSpecifier>::GetterType {
                     let offset = <::bit_field::BitField0 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
                         + <BitField1 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize;
@@ -779,6 +1905,16 @@ mod tests {
                         + <BitField1 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize;
                     self.set(offset, <BitField2 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH, val)
                 }
+                pub fn get_b_raw(&self) -> u64 {
+                    let offset = <::bit_field::BitField0 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                        + <BitField1 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize;
+                    self.get(offset, <BitField2 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH)
+                }
+                pub fn swap_b(&mut self, val: <BitField2 as ::bit_field::BitFieldSpecifier>::SetterType) -> <BitField2 as ::bit_field::BitFieldSpecifier>::GetterType {
+                    let old = self.get_b();
+                    self.set_b(val);
+                    old
+                }
                 pub fn get_c(&self) -> <BitField5 as ::bit_field::BitFieldSpecifier>::GetterType {
                     let offset = <::bit_field::BitField0 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
                         + <BitField1 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
@@ -794,6 +1930,33 @@ mod tests {
                         + <BitField2 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize;
                     self.set(offset, <BitField5 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH, val)
                 }
+                pub fn get_c_raw(&self) -> u64 {
+                    let offset = <::bit_field::BitField0 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                        + <BitField1 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize
+                        + <BitField2 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH as usize;
+                    self.get(offset, <BitField5 as ::bit_field::BitFieldSpecifier>::FIELD_WIDTH)
+                }
+                pub fn swap_c(&mut self, val: <BitField5 as ::bit_field::BitFieldSpecifier>::SetterType) -> <BitField5 as ::bit_field::BitFieldSpecifier>::GetterType {
+                    let old = self.get_c();
+                    self.set_c(val);
+                    old
+                }
+                pub fn diff(&self, other: &Self) -> Vec<(&'static str, u64, u64)> {
+                    let mut diffs = Vec::new();
+                    let (ours, theirs) = (self.get_a_raw(), other.get_a_raw());
+                    if ours != theirs {
+                        diffs.push(("a", ours, theirs));
+                    }
+                    let (ours, theirs) = (self.get_b_raw(), other.get_b_raw());
+                    if ours != theirs {
+                        diffs.push(("b", ours, theirs));
+                    }
+                    let (ours, theirs) = (self.get_c_raw(), other.get_c_raw());
+                    if ours != theirs {
+                        diffs.push(("c", ours, theirs));
+                    }
+                    diffs
+                }
             }
             impl std::fmt::Debug for MyBitField {
                 fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -807,7 +1970,9 @@ mod tests {
         };
 
         assert_eq!(
-            bitfield_impl(&input).unwrap().to_string(),
+            bitfield_impl(&input, &BitfieldArgs::default())
+                .unwrap()
+                .to_string(),
             expected.to_string()
         );
     }
@@ -56,9 +56,33 @@
 //!
 //! ```ignore
 //! impl MyFourBytes {
+//!     // Size of the struct, here 32 and 4.
+//!     pub const TOTAL_BITS: usize;
+//!     pub const TOTAL_BYTES: usize;
+//!
 //!     // Initializes all fields to 0.
 //!     pub fn new() -> Self;
 //!
+//!     // The bytes of the struct, TOTAL_BYTES long.
+//!     pub fn as_bytes(&self) -> &[u8];
+//!
+//!     // Conversions to and from an unsigned integer of the same size (here u32),
+//!     // see `BitFieldPrimitive`:
+//!     pub fn to_le<T: BitFieldPrimitive>(&self) -> T;
+//!     pub fn to_be<T: BitFieldPrimitive>(&self) -> T;
+//!     pub fn from_le<T: BitFieldPrimitive>(val: T) -> Self;
+//!     pub fn from_be<T: BitFieldPrimitive>(val: T) -> Self;
+//!
+//!     // Conversion from bytes without checking the fields, see below:
+//!     pub fn from_bytes(bytes: [u8; 4]) -> Self;
+//!
+//!     // Conversions to and from little-endian 64-bit words, e.g. to write the
+//!     // struct to hardware a word at a time. Unless TOTAL_BYTES is a multiple
+//!     // of 8, the last word only holds the remaining bytes, zero-extended when
+//!     // converting to words and truncated when converting from them:
+//!     pub fn to_le_u64_array(&self) -> [u64; 1];
+//!     pub fn from_le_u64_array(words: [u64; 1]) -> Self;
+//!
 //!     // Field getters and setters:
 //!     pub fn get_a(&self) -> u8;
 //!     pub fn set_a(&mut self, val: u8);
@@ -69,6 +93,27 @@
 //!     pub fn get_d(&self) -> u32;
 //!     pub fn set_d(&mut self, val: u32);
 //!
+//!     // Unconverted field bits:
+//!     pub fn get_a_raw(&self) -> u64;
+//!     pub fn get_b_raw(&self) -> u64;
+//!     pub fn get_c_raw(&self) -> u64;
+//!     pub fn get_d_raw(&self) -> u64;
+//!
+//!     // Setters returning the previous value of the field:
+//!     pub fn swap_a(&mut self, val: u8) -> u8;
+//!     pub fn swap_b(&mut self, val: u8) -> u8;
+//!     pub fn swap_c(&mut self, val: u8) -> u8;
+//!     pub fn swap_d(&mut self, val: u32) -> u32;
+//!
+//!     // Names and raw values of the fields that differ from `other`, as
+//!     // (name, value in self, value in other):
+//!     pub fn diff(&self, other: &Self) -> Vec<(&'static str, u64, u64)>;
+//!
+//!     // Borrowed bytes of byte-aligned fields, and setters copying them in,
+//!     // see below:
+//!     pub fn get_d_bytes(&self) -> &[u8];
+//!     pub fn set_d_bytes(&mut self, bytes: &[u8]);
+//!
 //!     // Bit-level accessors:
 //!     pub fn get_bit(&self, offset: usize) -> bool;
 //!     pub fn set_bit(&mut self, offset: usize, val: bool);
@@ -77,6 +122,58 @@
 //! }
 //! ```
 //!
+//! Fields whose offset and width are both multiples of 8 also get a
+//! `get_<field>_bytes` getter returning a slice of the struct's bytes, without
+//! copying them into an integer, and a `set_<field>_bytes` setter copying a
+//! slice into them. The setter panics if the slice is not exactly as long as
+//! the field. The layout must be known when the macro is
+//! expanded, so every field up to and including this one must be `bool`, `BN`,
+//! `BitFieldN`, or have a `#[bits = N]` attribute.
+//!
+//! ```
+//! use bit_field::*;
+//!
+//! #[bitfield]
+//! struct Header {
+//!     flags: B16,
+//!     mac: B48,
+//! }
+//!
+//! let mut header = Header::new();
+//! header.set_mac(0x665544332211);
+//! assert_eq!(header.get_mac_bytes(), &[0x11, 0x22, 0x33, 0x44, 0x55, 0x66]);
+//! header.set_mac_bytes(&[0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff]);
+//! assert_eq!(header.get_mac(), 0xffeeddccbbaa);
+//! ```
+//!
+//! Like `from_le` and `from_be`, `from_bytes` takes whatever bits it is given,
+//! so enum fields may hold values that match no variant. For bytes that can't
+//! be trusted, structs also implement `TryFrom<[u8; TOTAL_BYTES]>`, which fails
+//! with an `InvalidFieldValue` naming the first field that can't be decoded.
+//!
+//! ```
+//! use bit_field::*;
+//!
+//! #[bitfield]
+//! #[bits = 2]
+//! #[derive(Debug, PartialEq)]
+//! enum Kind {
+//!     Data = 0,
+//!     Control = 1,
+//! }
+//!
+//! #[bitfield]
+//! struct Packet {
+//!     kind: Kind,
+//!     len: B6,
+//! }
+//!
+//! assert!(Packet::try_from([0x41]).is_ok());
+//! let err = Packet::try_from([0x43]).unwrap_err();
+//! assert_eq!(err.field(), "kind");
+//! assert_eq!(err.raw_val(), 3);
+//! ```
+//!
 //! # Bit field specifier types
 //!
 //! Field types may be specified as B1 through B64, or alternatively as
@@ -123,7 +220,8 @@
 //!
 //! The enum has `#[bits = N]` attributes with it. `N` will be the width of the field. The getter
 //! function of this enum field will return `Result<EnumType, u64>`. Raw value that does not match
-//! any variant will result in an `Err(u64)`.
+//! any variant will result in an `Err(u64)`. The discriminants only need to fit in `N` bits; they
+//! don't need to be contiguous.
 //!
 //! ```
 //! use bit_field::*;
@@ -143,6 +241,12 @@
 //!     two_bits: TwoBits,
 //!     suffix: BitField5,
 //! }
+//!
+//! let mut s = Struct::new();
+//! s.set(1, 2, 0b10);
+//! assert_eq!(s.get_two_bits().unwrap_err().raw_val(), 0b10);
+//! // The raw getter returns the bit pattern without trying to decode it.
+//! assert_eq!(s.get_two_bits_raw(), 0b10);
 //! ```
 //!
 //! The enum has a number of variants which is a power of 2 and the discriminant values
@@ -169,6 +273,72 @@
 //! }
 //! ```
 //!
+//! Bitfield enums keep their discriminants, so `as` casts still work on them. They
+//! also get a `discriminant(&self) -> u64` method, which works through a reference.
+//!
+//! ```
+//! use bit_field::*;
+//!
+//! #[bitfield]
+//! enum TwoBits {
+//!     Zero = 0b00,
+//!     One = 0b01,
+//!     Two = 0b10,
+//!     Three = 0b11,
+//! }
+//!
+//! assert_eq!(TwoBits::Two as u8, 0b10);
+//! assert_eq!(TwoBits::Two.discriminant(), 0b10);
+//! ```
+//!
+//! An enum may also declare its integer representation with `#[repr(u8)]`, `#[repr(u16)]`,
+//! `#[repr(u32)]` or `#[repr(u64)]`. Values are then converted through that type, and the field
+//! width must fit in it.
+//!
+//! ```
+//! use bit_field::*;
+//!
+//! #[bitfield]
+//! #[bits = 10]
+//! #[repr(u16)]
+//! #[derive(Debug, PartialEq)]
+//! enum TenBits {
+//!     Low = 0x001,
+//!     High = 0x3ff,
+//! }
+//!
+//! #[bitfield]
+//! struct Struct {
+//!     ten_bits: TenBits,
+//!     padding: BitField6,
+//! }
+//! ```
+//!
+//! An enum with `#[bits = N]` may also have a single `#[fallback]` variant carrying the raw value.
+//! Raw values that do not match any other variant are decoded into it, so the getter returns the
+//! enum type directly. The other variants must have explicit discriminants, which are removed from
+//! the emitted enum.
+//!
+//! ```
+//! use bit_field::*;
+//!
+//! #[bitfield]
+//! #[bits = 2]
+//! #[derive(Debug, PartialEq)]
+//! enum Command {
+//!     Read = 0b00,
+//!     Write = 0b01,
+//!     #[fallback]
+//!     Unknown(u8),
+//! }
+//!
+//! #[bitfield]
+//! struct Struct {
+//!     command: Command,
+//!     padding: BitField6,
+//! }
+//! ```
+//!
 //! An optional `#[bits = N]` attribute may be used to document the number of
 //! bits in any field. This is intended for fields of enum type whose name does
 //! not clearly indicate the number of bits. The attribute is optional but helps
@@ -196,6 +366,51 @@
 //! }
 //! ```
 //!
+//! More generally, a field may be of any type implementing `BitFieldSpecifier`,
+//! whose `FIELD_WIDTH` is the width of the field and whose `GetterType` and
+//! `SetterType` are what its accessors take and return. For example, a newtype
+//! can wrap one of the types above to give some of its fields a distinct type.
+//!
+//! ```
+//! use bit_field::*;
+//!
+//! #[bitfield]
+//! #[bits = 2]
+//! #[derive(Debug, PartialEq)]
+//! enum Level {
+//!     Low = 0,
+//!     High = 1,
+//! }
+//!
+//! #[derive(Debug, PartialEq)]
+//! struct Target(Level);
+//!
+//! impl BitFieldSpecifier for Target {
+//!     const FIELD_WIDTH: u8 = Level::FIELD_WIDTH;
+//!     type SetterType = Target;
+//!     type GetterType = Option<Target>;
+//!
+//!     fn from_u64(val: u64) -> Option<Target> {
+//!         Level::from_u64(val).ok().map(Target)
+//!     }
+//!
+//!     fn into_u64(val: Target) -> u64 {
+//!         Level::into_u64(val.0)
+//!     }
+//! }
+//!
+//! #[bitfield]
+//! struct Control {
+//!     current: Level,
+//!     target: Target,
+//!     reserved: B4,
+//! }
+//!
+//! let mut control = Control::new();
+//! control.set_target(Target(Level::High));
+//! assert_eq!(control.get_target(), Some(Target(Level::High)));
+//! ```
+//!
 //! # Derives
 //!
 //! Derives may be specified and are applied to the data structure post
@@ -212,14 +427,198 @@
 //! }
 //! ```
 //!
+//! # Generic structs
+//!
+//! A bitfield struct may take one type parameter bounded by
+//! `BitFieldSpecifier` to reuse a layout with different field types. Fields of
+//! that type need a #[bits = N] attribute, since the size of the struct can't
+//! depend on the parameter. It is checked against the real width when the
+//! struct is used with a concrete type.
+//!
+//! ```
+//! use bit_field::*;
+//!
+//! #[bitfield]
+//! #[derive(Debug, PartialEq)]
+//! enum Mode {
+//!     Off = 0,
+//!     On = 1,
+//! }
+//!
+//! #[bitfield]
+//! pub struct Reg<E: BitFieldSpecifier> {
+//!     #[bits = 1]
+//!     payload: E,
+//!     rest: B7,
+//! }
+//!
+//! let mut reg: Reg<Mode> = Reg::new();
+//! reg.set_payload(Mode::On);
+//! assert_eq!(reg.get_payload(), Mode::On);
+//! ```
+//!
+//! # Explicit offsets
+//!
+//! A field with an `#[offset = N]` attribute starts at bit `N` instead of right
+//! after the previous field, leaving the bits in between unused, which saves
+//! writing padding fields when following a datasheet. Fields still have to be
+//! declared in the order of their offsets: a field placed before the end of the
+//! previous one is a compile error.
+//!
+//! ```
+//! use bit_field::*;
+//!
+//! #[bitfield]
+//! pub struct Entry {
+//!     present: bool,
+//!     #[offset = 12]
+//!     frame: B20,
+//! }
+//!
+//! let mut entry = Entry::new();
+//! entry.set_frame(0xabcde);
+//! assert_eq!(entry.to_le::<u32>(), 0xabcde000);
+//! ```
+//!
+//! # Reserved fields
+//!
+//! Fields marked `#[mbz]` are reserved and must be zero. Structs with such
+//! fields get a `validate` function that checks them, for example on a value
+//! written by a guest, and reports the first one that is not zero.
+//!
+//! ```
+//! use bit_field::*;
+//!
+//! #[bitfield]
+//! pub struct Command {
+//!     opcode: B5,
+//!     #[mbz]
+//!     reserved: B3,
+//! }
+//!
+//! let mut cmd = Command::from_le(0x21u8);
+//! assert_eq!(cmd.validate().unwrap_err().field(), "reserved");
+//! cmd.set_reserved(0);
+//! assert!(cmd.validate().is_ok());
+//! ```
+//!
+//! # Accessor attributes
+//!
+//! `#[accessor_attr(...)]` on a field puts the attribute in parentheses on the
+//! generated `get_<field>` and `set_<field>` functions, for example to mark the
+//! getter of a register whose read has side effects as `must_use`. It may be
+//! repeated for several attributes. `must_use` is only put on the getter, since
+//! the setter returns nothing.
+//!
+//! ```
+//! use bit_field::*;
+//!
+//! #[bitfield]
+//! pub struct Status {
+//!     #[accessor_attr(must_use)]
+//!     pending: B4,
+//!     rest: B4,
+//! }
+//!
+//! let status = Status::new();
+//! assert_eq!(status.get_pending(), 0);
+//! ```
+//!
+//! # Transforming field values
+//!
+//! `#[transform(get = "decode", set = "encode")]` on a field makes its getter
+//! return `decode(value)` for the value it would otherwise return, and its
+//! setter store `encode(val)` instead of `val`, for fields whose stored bits
+//! encode the value, such as a size stored as its base 2 logarithm. Both
+//! functions take and return the field's getter or setter type, and either may
+//! be left out. `get_<field>_raw` still returns the stored bits.
+//!
+//! ```
+//! use bit_field::*;
+//!
+//! fn decode_size(log2: u8) -> u8 {
+//!     1 << log2
+//! }
+//!
+//! fn encode_size(size: u8) -> u8 {
+//!     size.trailing_zeros() as u8
+//! }
+//!
+//! #[bitfield]
+//! pub struct Burst {
+//!     #[transform(get = "decode_size", set = "encode_size")]
+//!     size: B4,
+//!     count: B4,
+//! }
+//!
+//! let mut burst = Burst::new();
+//! burst.set_size(32);
+//! assert_eq!(burst.get_size(), 32);
+//! assert_eq!(burst.get_size_raw(), 5);
+//! ```
+//!
+//! # Comparing by fields
+//!
+//! A derived `PartialEq` or `Hash` covers every bit of the struct, including
+//! the bits left unused by `#[offset = N]` and the `#[mbz]` fields.
+//! `#[bitfield(eq_by_fields)]` instead generates `PartialEq`, `Eq` and `Hash`
+//! that only look at the other fields, so values that differ only in reserved
+//! bits are equal and hash equally, e.g. as `HashMap` keys. These traits must
+//! not also be derived.
+//!
+//! ```
+//! use bit_field::*;
+//!
+//! #[bitfield(eq_by_fields)]
+//! pub struct Key {
+//!     id: B4,
+//!     #[mbz]
+//!     reserved: B4,
+//! }
+//!
+//! let a = Key::new();
+//! let mut b = Key::new();
+//! b.set_reserved(0xf);
+//! assert!(a == b);
+//! ```
+//!
+//! # Tracking initialization
+//!
+//! `#[bitfield(track_init)]` makes debug builds remember which bytes of the
+//! struct have been written since `new()`. Reading a field that overlaps a byte
+//! that was never written fails a `debug_assert`. Only bytes are tracked, so a
+//! field sharing a byte with one that was set is not caught. Structs made with
+//! `from_le` or `from_be` count as fully written. Release builds generate the
+//! same code as plain `#[bitfield]`.
+//!
+//! In debug builds the tracking state is a hidden field of the struct, so it is
+//! seen by derives such as `PartialEq`, and the `Debug` output reads every
+//! field.
+//!
+//! ```
+//! use bit_field::*;
+//!
+//! #[bitfield(track_init)]
+//! pub struct Tracked {
+//!     low: B4,
+//!     high: B4,
+//!     next: B8,
+//! }
+//!
+//! let mut t = Tracked::new();
+//! t.set_low(1);
+//! assert_eq!(t.get_high(), 0); // same byte as `low`
+//! // t.get_next() would panic in a debug build.
+//! ```
+//!
 //! # Compile time checks
 //!
 //! If the total size is not a multiple of 8 bits, you will receive an error
-//! message at compile time mentioning:
+//! at compile time that names the total:
 //!
-//! > the trait `bit_field::checks::TotalSizeIsMultipleOfEightBits` is not implemented
+//! > total bitfield size is 10 bits, which is not a multiple of 8
 //!
-//! ```compile_fail
+//! ```compile_fail,E0080
 //! use bit_field::*;
 //!
 //! #[bitfield]
@@ -245,6 +644,20 @@
 //! }
 //! ```
 //!
+//! If a bitfield enum is wider than its declared repr, it will be caught.
+//!
+//! ```compile_fail
+//! use bit_field::*;
+//!
+//! #[bitfield]
+//! #[bits = 10]
+//! #[repr(u8)] // error
+//! enum Broken {
+//!     Zero = 0,
+//!     One = 1,
+//! }
+//! ```
+//!
 //! If the value provided in a #[bits = N] attribute does not match the real
 //! number of bits in that field, it will be caught.
 //!
@@ -265,6 +678,108 @@
 //!     padding: BitField7,
 //! }
 //! ```
+//!
+//! When every field has a #[bits = N] attribute, the attributes must also add
+//! up to a multiple of 8, and the error names the total.
+//!
+//! ```compile_fail
+//! use bit_field::*;
+//!
+//! #[bitfield]
+//! struct Struct {
+//!     #[bits = 3]
+//!     low: B3,
+//!     #[bits = 6] // error: adds up to 9 bits
+//!     high: B5,
+//! }
+//! ```
+//!
+//! For a generic field, the #[bits = N] attribute is checked once the struct is
+//! used with a concrete type.
+//!
+//! ```compile_fail
+//! use bit_field::*;
+//!
+//! #[bitfield]
+//! struct Reg<E: BitFieldSpecifier> {
+//!     #[bits = 2]
+//!     payload: E,
+//!     rest: B6,
+//! }
+//!
+//! let reg: Reg<B3> = Reg::new(); // error: B3 is 3 bits wide
+//! reg.get_payload();
+//! ```
+//!
+//! Fields of the zero-width types `B0` and `BitField0` are rejected, since they
+//! take no bits and usually hide a mistake in the layout.
+//!
+//! ```compile_fail
+//! use bit_field::*;
+//!
+//! #[bitfield]
+//! struct Struct {
+//!     data: B8,
+//!     nothing: B0, // error: zero-width field
+//! }
+//! ```
+//!
+//! Fields placed with #[offset = N] must not overlap the previous fields.
+//!
+//! ```compile_fail
+//! use bit_field::*;
+//!
+//! #[bitfield]
+//! struct Entry {
+//!     flags: B4,
+//!     #[offset = 2] // error: `flags` ends at bit 4
+//!     kind: B4,
+//! }
+//! ```
+//!
+//! Attributes given with #[accessor_attr(...)] apply to the generated
+//! functions, so ignoring the result of a `must_use` getter is reported.
+//!
+//! ```compile_fail
+//! #![deny(unused_must_use)]
+//! use bit_field::*;
+//!
+//! #[bitfield]
+//! struct Status {
+//!     #[accessor_attr(must_use)]
+//!     pending: B8,
+//! }
+//!
+//! let status = Status::new();
+//! status.get_pending(); // error: unused return value
+//! ```
+//!
+//! To catch a layout changing size by accident, `assert_byte_size` checks the
+//! size of a struct in a constant, so a mismatch fails the build.
+//!
+//! ```
+//! use bit_field::*;
+//!
+//! #[bitfield]
+//! struct Reg {
+//!     enable: bool,
+//!     addr: B31,
+//! }
+//!
+//! const _: () = assert_byte_size::<Reg, 4>();
+//! ```
+//!
+//! ```compile_fail
+//! use bit_field::*;
+//!
+//! #[bitfield]
+//! struct Reg {
+//!     enable: bool,
+//!     addr: B39,
+//! }
+//!
+//! const _: () = assert_byte_size::<Reg, 4>(); // error: Reg is 5 bytes
+//! ```
 
 use std::fmt;
 use std::fmt::Display;
@@ -300,6 +815,75 @@ impl Display for Error {
 
 impl std::error::Error for Error {}
 
+/// Error returned by the generated `validate` function when a `#[mbz]` field is not zero.
+#[derive(Debug, PartialEq, Eq)]
+pub struct MbzViolation {
+    field: &'static str,
+    val: u64,
+}
+
+impl MbzViolation {
+    pub fn new(field: &'static str, val: u64) -> MbzViolation {
+        MbzViolation { field, val }
+    }
+
+    /// Name of the field that is not zero.
+    pub fn field(&self) -> &'static str {
+        self.field
+    }
+
+    pub fn raw_val(&self) -> u64 {
+        self.val
+    }
+}
+
+impl Display for MbzViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "reserved field {} must be zero but is {:#x}",
+            self.field, self.val
+        )
+    }
+}
+
+impl std::error::Error for MbzViolation {}
+
+/// Error returned when converting bytes to a bitfield struct with `TryFrom` and a field holds a
+/// value that its type can't decode, such as an enum field that matches no variant.
+#[derive(Debug, PartialEq, Eq)]
+pub struct InvalidFieldValue {
+    field: &'static str,
+    val: u64,
+}
+
+impl InvalidFieldValue {
+    pub fn new(field: &'static str, val: u64) -> InvalidFieldValue {
+        InvalidFieldValue { field, val }
+    }
+
+    /// Name of the field with the invalid value.
+    pub fn field(&self) -> &'static str {
+        self.field
+    }
+
+    pub fn raw_val(&self) -> u64 {
+        self.val
+    }
+}
+
+impl Display for InvalidFieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "field {} has an invalid value {:#x}",
+            self.field, self.val
+        )
+    }
+}
+
+impl std::error::Error for InvalidFieldValue {}
+
 #[doc(hidden)]
 pub trait BitFieldSpecifier {
     // Width of this field in bits.
@@ -315,6 +899,164 @@ pub trait BitFieldSpecifier {
 
     fn from_u64(val: u64) -> Self::GetterType;
     fn into_u64(val: Self::SetterType) -> u64;
+
+    // Whether `from_u64` can decode `val`. Only enums with #[bits = N] and without a #[fallback]
+    // variant have values that it can't.
+    fn is_valid(_val: u64) -> bool {
+        true
+    }
+}
+
+/// Bit-level access to the data of a `#[bitfield]` struct, implemented for every such struct.
+///
+/// This is what the `get` and `set` methods of the struct are built on, and allows writing code
+/// that works with any bitfield struct.
+pub trait BitFieldAccess {
+    /// Returns `width` bits starting at bit `offset`.
+    fn get(&self, offset: usize, width: u8) -> u64;
+    /// Sets `width` bits starting at bit `offset` to the low bits of `val`.
+    fn set(&mut self, offset: usize, width: u8, val: u64);
+}
+
+/// A `#[bitfield]` struct as a whole, implemented for every such struct, to write code that
+/// accepts any of them. The `get` and `set` methods come from `BitFieldAccess`.
+///
+/// ```
+/// use bit_field::*;
+///
+/// #[bitfield]
+/// struct Flags {
+///     a: bool,
+///     b: bool,
+///     rest: B6,
+/// }
+///
+/// fn set_bits<T: Bitfield>(bitfield: &T) -> Vec<usize> {
+///     (0..T::bits()).filter(|i| bitfield.get_bit(*i)).collect()
+/// }
+///
+/// let mut flags = Flags::new();
+/// flags.set_b(true);
+/// assert_eq!(set_bits(&flags), vec![1]);
+/// ```
+pub trait Bitfield: BitFieldAccess {
+    /// Size of the struct in bytes, the same as its `TOTAL_BYTES`.
+    const TOTAL_BYTES: usize;
+    /// Size of the struct in bits, the same as its `TOTAL_BITS`.
+    fn bits() -> usize;
+    /// The bytes of the struct.
+    fn as_bytes(&self) -> &[u8];
+    /// Returns the bit at `offset`.
+    fn get_bit(&self, offset: usize) -> bool;
+    /// Sets the bit at `offset` to `val`.
+    fn set_bit(&mut self, offset: usize, val: bool);
+}
+
+/// Copies `width` bits starting at bit `src_offset` of `src` into `dst` starting at bit
+/// `dst_offset`. `width` may be at most 64.
+///
+/// ```
+/// use bit_field::*;
+///
+/// #[bitfield]
+/// struct Src {
+///     low: B4,
+///     addr: B12,
+/// }
+///
+/// #[bitfield]
+/// struct Dst {
+///     addr: B12,
+///     high: B4,
+/// }
+///
+/// let mut src = Src::new();
+/// src.set_addr(0xabc);
+/// let mut dst = Dst::new();
+/// copy_bits(&mut dst, 0, &src, 4, 12);
+/// assert_eq!(dst.get_addr(), 0xabc);
+/// ```
+pub fn copy_bits<D, S>(dst: &mut D, dst_offset: usize, src: &S, src_offset: usize, width: u8)
+where
+    D: BitFieldAccess + ?Sized,
+    S: BitFieldAccess + ?Sized,
+{
+    assert!(width <= 64, "cannot copy more than 64 bits at once");
+    dst.set(dst_offset, width, src.get(src_offset, width));
+}
+
+/// An unsigned integer type that a `#[bitfield]` struct of the same size can be converted to and
+/// from with the generated `to_le`, `to_be`, `from_le` and `from_be` functions.
+///
+/// The little-endian conversions keep bit `n` of the struct as bit `n` of the integer, like
+/// `get(0, N)` does. The big-endian ones treat the first byte of the struct as the most
+/// significant, as for a register of a big-endian device.
+///
+/// ```
+/// use bit_field::*;
+///
+/// #[bitfield]
+/// struct Register {
+///     low: B8,
+///     high: B8,
+/// }
+///
+/// let mut reg = Register::new();
+/// reg.set_low(0x12);
+/// reg.set_high(0x34);
+/// assert_eq!(reg.to_le::<u16>(), 0x3412);
+/// assert_eq!(reg.to_be::<u16>(), 0x1234);
+/// assert_eq!(Register::from_be(0x1234u16).get_low(), 0x12);
+/// ```
+pub trait BitFieldPrimitive: Sized {
+    /// Byte array with the size of the integer.
+    type Bytes;
+
+    fn from_le_bytes(bytes: Self::Bytes) -> Self;
+    fn from_be_bytes(bytes: Self::Bytes) -> Self;
+    fn to_le_bytes(self) -> Self::Bytes;
+    fn to_be_bytes(self) -> Self::Bytes;
+}
+
+macro_rules! impl_bit_field_primitive {
+    ($($ty:ty),*) => {
+        $(
+            impl BitFieldPrimitive for $ty {
+                type Bytes = [u8; std::mem::size_of::<$ty>()];
+
+                #[inline]
+                fn from_le_bytes(bytes: Self::Bytes) -> Self {
+                    <$ty>::from_le_bytes(bytes)
+                }
+
+                #[inline]
+                fn from_be_bytes(bytes: Self::Bytes) -> Self {
+                    <$ty>::from_be_bytes(bytes)
+                }
+
+                #[inline]
+                fn to_le_bytes(self) -> Self::Bytes {
+                    <$ty>::to_le_bytes(self)
+                }
+
+                #[inline]
+                fn to_be_bytes(self) -> Self::Bytes {
+                    <$ty>::to_be_bytes(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_bit_field_primitive!(u8, u16, u32, u64);
+
+/// Fails to compile when used in a constant, as in `const _: () = assert_byte_size::<T, N>();`,
+/// unless the bitfield struct `T` is `N` bytes long.
+pub const fn assert_byte_size<T: Bitfield, const N: usize>() {
+    assert!(
+        T::TOTAL_BYTES == N,
+        "bitfield struct has an unexpected size"
+    );
 }
 
 // Largest u64 representable by this bit field specifier. Used by generated code
@@ -348,14 +1090,48 @@ impl BitFieldSpecifier for bool {
     }
 }
 
-// Instantiated by the generated code to prove that the total size of fields is
-// a multiple of 8 bits.
+// Evaluated in a constant by the generated code to check that the total size of
+// fields is a multiple of 8 bits, failing with a message that names the total.
 #[doc(hidden)]
-pub struct Check<T: checks::TotalSizeIsMultipleOfEightBits> {
-    marker: std::marker::PhantomData<T>,
-}
+pub const fn check_total_bits(bits: usize) {
+    if bits % 8 == 0 {
+        return;
+    }
 
-mod checks {
-    pub trait TotalSizeIsMultipleOfEightBits {}
-    impl TotalSizeIsMultipleOfEightBits for [u8; 0] {}
+    const PREFIX: &[u8] = b"total bitfield size is ";
+    const SUFFIX: &[u8] = b" bits, which is not a multiple of 8";
+    // 20 digits are enough for any usize.
+    let mut msg = [0u8; PREFIX.len() + 20 + SUFFIX.len()];
+    let mut len = 0;
+    let mut i = 0;
+    while i < PREFIX.len() {
+        msg[len] = PREFIX[i];
+        len += 1;
+        i += 1;
+    }
+    let mut digits = 1;
+    let mut rest = bits / 10;
+    while rest > 0 {
+        digits += 1;
+        rest /= 10;
+    }
+    rest = bits;
+    i = digits;
+    while i > 0 {
+        i -= 1;
+        msg[len + i] = b'0' + (rest % 10) as u8;
+        rest /= 10;
+    }
+    len += digits;
+    i = 0;
+    while i < SUFFIX.len() {
+        msg[len] = SUFFIX[i];
+        len += 1;
+        i += 1;
+    }
+
+    match std::str::from_utf8(msg.split_at(len).0) {
+        Ok(msg) => panic!("{}", msg),
+        Err(_) => panic!("total bitfield size is not a multiple of 8"),
+    }
 }
@@ -0,0 +1,25 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+#![deny(unused_must_use)]
+
+use bit_field::*;
+
+#[bitfield]
+struct Interrupt {
+    #[accessor_attr(must_use)]
+    #[accessor_attr(inline)]
+    pending: B7,
+    enable: bool,
+}
+
+#[test]
+fn test_accessor_attr() {
+    let mut irq = Interrupt::new();
+    irq.set_pending(0x41);
+    irq.set_enable(true);
+    let pending = irq.get_pending();
+    assert_eq!(pending, 0x41);
+    assert!(irq.get_enable());
+}
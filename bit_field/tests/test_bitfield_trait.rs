@@ -0,0 +1,53 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use bit_field::*;
+
+#[bitfield]
+struct Small {
+    low: B4,
+    high: B4,
+}
+
+#[bitfield]
+struct Large {
+    flag: bool,
+    value: B15,
+    tail: B16,
+}
+
+const _: () = assert_byte_size::<Small, 1>();
+const _: () = assert_byte_size::<Large, 4>();
+
+// Formats any bitfield struct as its bits, most significant first.
+fn dump<T: Bitfield>(bitfield: &T) -> String {
+    (0..T::bits())
+        .rev()
+        .map(|i| if bitfield.get_bit(i) { '1' } else { '0' })
+        .collect()
+}
+
+fn fill<T: Bitfield>(bitfield: &mut T, offset: usize, width: u8) {
+    bitfield.set(offset, width, u64::MAX >> (64 - width));
+}
+
+#[test]
+fn test_bitfield_trait() {
+    let mut small = Small::new();
+    small.set_low(0b1010);
+    assert_eq!(dump(&small), "00001010");
+    fill(&mut small, 4, 4);
+    assert_eq!(small.get_high(), 0xf);
+
+    let mut large = Large::new();
+    large.set_flag(true);
+    large.set_tail(0x8001);
+    assert_eq!(dump(&large), "10000000000000010000000000000001");
+    assert_eq!(Bitfield::as_bytes(&large), &[0x01, 0x00, 0x01, 0x80]);
+    assert_eq!(<Large as Bitfield>::TOTAL_BYTES, 4);
+
+    Bitfield::set_bit(&mut large, 1, true);
+    assert_eq!(large.get_value(), 1);
+    assert_eq!(large.get(0, 16), 0x3);
+}
@@ -0,0 +1,51 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use bit_field::*;
+
+#[bitfield]
+struct Packet {
+    valid: bool,
+    kind: B7,
+    mac: B48,
+    #[bits = 8]
+    length: BitField8,
+}
+
+#[test]
+fn test_bytes() {
+    let mut packet = Packet::new();
+    packet.set_valid(true);
+    packet.set_kind(0x7f);
+    packet.set_mac(0x0605_0403_0201);
+    packet.set_length(0xaa);
+
+    assert_eq!(
+        packet.get_mac_bytes(),
+        &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06]
+    );
+    assert_eq!(packet.get_length_bytes(), &[0xaa]);
+}
+
+#[test]
+fn test_set_bytes() {
+    let mut packet = Packet::new();
+    packet.set_kind(0x7f);
+    packet.set_mac_bytes(&[0x52, 0x54, 0x00, 0x12, 0x34, 0x56]);
+
+    assert_eq!(
+        packet.get_mac_bytes(),
+        &[0x52, 0x54, 0x00, 0x12, 0x34, 0x56]
+    );
+    assert_eq!(packet.get_mac(), 0x5634_1200_5452);
+    assert_eq!(packet.get_kind(), 0x7f);
+    assert_eq!(packet.get_length(), 0);
+}
+
+#[test]
+#[should_panic(expected = "field `mac` is 6 bytes long")]
+fn test_set_bytes_wrong_length() {
+    let mut packet = Packet::new();
+    packet.set_mac_bytes(&[0; 4]);
+}
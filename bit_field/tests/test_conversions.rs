@@ -0,0 +1,49 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use bit_field::*;
+
+#[bitfield]
+struct Register {
+    enable: bool,
+    mode: B3,
+    count: B12,
+    address: B16,
+}
+
+#[test]
+fn test_le_be_conversions() {
+    let mut reg = Register::new();
+    reg.set_enable(true);
+    reg.set_mode(0b101);
+    reg.set_count(0xabc);
+    reg.set_address(0x1234);
+
+    // Bytes in memory: 0xcb 0xab 0x34 0x12.
+    assert_eq!(reg.to_le::<u32>(), 0x1234_abcb);
+    assert_eq!(reg.to_be::<u32>(), 0xcbab_3412);
+    assert_eq!(reg.to_le::<u32>(), reg.get(0, 32) as u32);
+
+    let reg = Register::from_le(0x1234_abcbu32);
+    assert!(reg.get_enable());
+    assert_eq!(reg.get_mode(), 0b101);
+    assert_eq!(reg.get_count(), 0xabc);
+    assert_eq!(reg.get_address(), 0x1234);
+
+    let reg = Register::from_be(0xcbab_3412u32);
+    assert_eq!(reg.get_count(), 0xabc);
+    assert_eq!(reg.get_address(), 0x1234);
+    assert_eq!(reg.to_le::<u32>(), 0x1234_abcb);
+}
+
+#[test]
+fn test_total_size() {
+    assert_eq!(Register::TOTAL_BITS, 32);
+    assert_eq!(Register::TOTAL_BYTES * 8, Register::TOTAL_BITS);
+    assert_eq!(Register::new().as_bytes().len(), Register::TOTAL_BYTES);
+
+    // Usable in constant expressions.
+    const BUF: [u8; Register::TOTAL_BYTES] = [0; Register::TOTAL_BYTES];
+    assert_eq!(BUF.len(), 4);
+}
@@ -0,0 +1,40 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use bit_field::*;
+
+#[bitfield]
+struct Source {
+    flags: B3,
+    address: B12,
+    tail: B9,
+}
+
+#[bitfield]
+struct Dest {
+    address: B12,
+    reserved: B20,
+}
+
+#[test]
+fn test_copy_bits() {
+    let mut src = Source::new();
+    src.set_flags(0b111);
+    src.set_address(0xabc);
+    src.set_tail(0x1ff);
+
+    let mut dst = Dest::new();
+    dst.set_reserved(0xfffff);
+    copy_bits(&mut dst, 0, &src, 3, 12);
+
+    assert_eq!(dst.get_address(), 0xabc);
+    assert_eq!(dst.get_reserved(), 0xfffff);
+
+    // Copy back to the source at the same location after clearing it.
+    src.set_address(0);
+    copy_bits(&mut src, 3, &dst, 0, 12);
+    assert_eq!(src.get_flags(), 0b111);
+    assert_eq!(src.get_address(), 0xabc);
+    assert_eq!(src.get_tail(), 0x1ff);
+}
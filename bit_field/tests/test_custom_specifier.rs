@@ -0,0 +1,63 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use bit_field::*;
+
+#[bitfield]
+#[bits = 2]
+#[derive(Debug, PartialEq)]
+enum Level {
+    Low = 0,
+    Mid = 1,
+    High = 2,
+}
+
+// A level requested by the driver, kept apart from the level the device reports.
+#[derive(Debug, PartialEq)]
+struct Requested(Level);
+
+impl BitFieldSpecifier for Requested {
+    const FIELD_WIDTH: u8 = Level::FIELD_WIDTH;
+    type SetterType = Requested;
+    type GetterType = Option<Requested>;
+
+    fn from_u64(val: u64) -> Option<Requested> {
+        Level::from_u64(val).ok().map(Requested)
+    }
+
+    fn into_u64(val: Requested) -> u64 {
+        Level::into_u64(val.0)
+    }
+
+    fn is_valid(val: u64) -> bool {
+        Level::is_valid(val)
+    }
+}
+
+#[bitfield]
+struct Control {
+    current: Level,
+    requested: Requested,
+    count: B4,
+}
+
+#[test]
+fn test_custom_specifier() {
+    assert_eq!(Control::TOTAL_BYTES, 1);
+
+    let mut control = Control::new();
+    control.set_current(Level::Mid);
+    control.set_requested(Requested(Level::High));
+    control.set_count(5);
+    assert_eq!(control.get_current().unwrap(), Level::Mid);
+    assert_eq!(control.get_requested(), Some(Requested(Level::High)));
+    assert_eq!(control.get_count(), 5);
+    assert_eq!(control.get(2, 2), 0b10);
+
+    control.set(2, 2, 0b11);
+    assert_eq!(control.get_requested(), None);
+    let err = Control::try_from(control.to_le::<u8>().to_le_bytes()).unwrap_err();
+    assert_eq!(err.field(), "requested");
+    assert_eq!(err.raw_val(), 0b11);
+}
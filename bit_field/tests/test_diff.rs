@@ -0,0 +1,42 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use bit_field::*;
+
+#[bitfield]
+#[derive(Debug, PartialEq)]
+enum Mode {
+    Idle = 0,
+    Run = 1,
+}
+
+#[bitfield]
+struct Control {
+    enable: bool,
+    mode: Mode,
+    count: B6,
+    address: B24,
+}
+
+#[test]
+fn test_diff() {
+    let mut before = Control::new();
+    before.set_count(3);
+    before.set_address(0x1234);
+    let mut after = Control::new();
+    after.set_count(3);
+    after.set_address(0x1234);
+    assert!(before.diff(&after).is_empty());
+
+    after.set_mode(Mode::Run);
+    after.set_address(0x5678);
+    assert_eq!(
+        before.diff(&after),
+        vec![("mode", 0, 1), ("address", 0x1234, 0x5678)]
+    );
+    assert_eq!(
+        after.diff(&before),
+        vec![("mode", 1, 0), ("address", 0x5678, 0x1234)]
+    );
+}
@@ -23,6 +23,15 @@ enum ThreeBits {
     Three = 0b111,
 }
 
+#[bitfield]
+#[bits = 10]
+#[repr(u16)]
+#[derive(Debug, PartialEq, Eq)]
+enum TenBits {
+    Low = 0x001,
+    High = 0x3ff,
+}
+
 #[bitfield]
 struct Struct {
     prefix: BitField1,
@@ -50,3 +59,118 @@ fn test_enum() {
     s.set_three_bits(ThreeBits::Two);
     assert_eq!(s.get(0, 8), 0b_1001_0010);
 }
+
+#[test]
+fn test_enum_raw() {
+    let mut s = Struct::new();
+    s.set_three_bits(ThreeBits::Three);
+    assert_eq!(s.get_three_bits_raw(), 0b111);
+
+    // 0b110 is not a ThreeBits variant, but the raw getter still returns it.
+    s.set(3, 3, 0b110);
+    assert_eq!(s.get_three_bits().unwrap_err().raw_val(), 0b110);
+    assert_eq!(s.get_three_bits_raw(), 0b110);
+    assert_eq!(s.get_two_bits_raw(), 0);
+}
+
+#[bitfield]
+struct ReprStruct {
+    prefix: BitField2,
+    ten_bits: TenBits,
+    suffix: BitField4,
+}
+
+#[test]
+fn test_repr_enum() {
+    let mut s = ReprStruct::new();
+    s.set_ten_bits(TenBits::High);
+    assert_eq!(s.get(0, 16), 0b_0000_1111_1111_1100);
+    assert_eq!(s.get_ten_bits().unwrap(), TenBits::High);
+
+    s.set_ten_bits(TenBits::Low);
+    assert_eq!(s.get(0, 16), 0b_0000_0000_0000_0100);
+    assert_eq!(s.get_ten_bits().unwrap(), TenBits::Low);
+
+    s.set(2, 10, 0x2);
+    assert_eq!(s.get_ten_bits().unwrap_err().raw_val(), 0x2);
+}
+
+#[bitfield]
+#[bits = 3]
+#[derive(Debug, PartialEq, Eq)]
+enum Sparse {
+    A = 0,
+    B = 1,
+    C = 4,
+    D = 5,
+}
+
+#[bitfield]
+struct SparseStruct {
+    sparse: Sparse,
+    suffix: BitField5,
+}
+
+#[test]
+fn test_sparse_enum() {
+    let mut s = SparseStruct::new();
+    for (variant, raw) in [
+        (Sparse::A, 0),
+        (Sparse::B, 1),
+        (Sparse::C, 4),
+        (Sparse::D, 5),
+    ] {
+        s.set(0, 3, raw);
+        assert_eq!(s.get_sparse().unwrap(), variant);
+        s.set_sparse(variant);
+        assert_eq!(s.get(0, 3), raw);
+    }
+
+    for raw in [2, 3, 6, 7] {
+        s.set(0, 3, raw);
+        assert_eq!(s.get_sparse().unwrap_err().raw_val(), raw);
+    }
+}
+
+#[bitfield]
+#[bits = 3]
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Read = 0b001,
+    Write = 0b010,
+    #[fallback]
+    Unknown(u8),
+}
+
+#[bitfield]
+struct CommandStruct {
+    command: Command,
+    suffix: BitField5,
+}
+
+#[test]
+fn test_fallback_enum() {
+    let mut s = CommandStruct::new();
+    s.set_command(Command::Write);
+    assert_eq!(s.get(0, 8), 0b010);
+    assert_eq!(s.get_command(), Command::Write);
+
+    s.set(0, 3, 0b110);
+    assert_eq!(s.get_command(), Command::Unknown(0b110));
+
+    s.set_command(Command::Unknown(0b101));
+    assert_eq!(s.get(0, 8), 0b101);
+}
+
+#[test]
+fn test_enum_discriminant() {
+    assert_eq!(TwoBits::Two as u8, 0b10);
+    assert_eq!(TwoBits::Two.discriminant(), 0b10);
+    assert_eq!(ThreeBits::Three as u8, 0b111);
+    assert_eq!(ThreeBits::Three.discriminant(), 0b111);
+    assert_eq!(TenBits::High as u16, 0x3ff);
+    assert_eq!(TenBits::High.discriminant(), 0x3ff);
+
+    assert_eq!(Command::Write.discriminant(), 0b010);
+    assert_eq!(Command::Unknown(0b110).discriminant(), 0b110);
+}
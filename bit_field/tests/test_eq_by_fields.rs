@@ -0,0 +1,43 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use std::collections::HashMap;
+
+use bit_field::*;
+
+#[bitfield(eq_by_fields)]
+struct Key {
+    id: B4,
+    #[mbz]
+    reserved: B4,
+    #[offset = 12]
+    tag: B4,
+}
+
+#[test]
+fn test_eq_by_fields() {
+    let mut a = Key::new();
+    a.set_id(3);
+    a.set_tag(9);
+
+    let mut b = Key::new();
+    b.set_id(3);
+    b.set_tag(9);
+    b.set_reserved(0xf);
+    // Bits 8 to 11 aren't part of any field.
+    b.set(8, 4, 0xa);
+    assert_ne!(a.to_le::<u16>(), b.to_le::<u16>());
+    assert!(a == b);
+
+    let mut map = HashMap::new();
+    map.insert(a, "first");
+    assert_eq!(map.insert(b, "second"), Some("first"));
+    assert_eq!(map.len(), 1);
+
+    let mut c = Key::new();
+    c.set_id(4);
+    c.set_tag(9);
+    map.insert(c, "third");
+    assert_eq!(map.len(), 2);
+}
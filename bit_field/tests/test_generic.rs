@@ -0,0 +1,58 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use bit_field::*;
+
+#[bitfield]
+#[derive(Debug, PartialEq, Eq)]
+enum Speed {
+    Low = 0,
+    Medium = 1,
+    High = 2,
+    Max = 3,
+}
+
+#[bitfield]
+#[derive(Debug, PartialEq, Eq)]
+enum Direction {
+    Up = 0,
+    Down = 1,
+    Left = 2,
+    Right = 3,
+}
+
+#[bitfield]
+struct Reg<E: BitFieldSpecifier> {
+    enable: bool,
+    #[bits = 2]
+    payload: E,
+    count: B5,
+}
+
+#[test]
+fn generic_payloads() {
+    let mut speed: Reg<Speed> = Reg::new();
+    speed.set_enable(true);
+    speed.set_payload(Speed::High);
+    speed.set_count(0x1f);
+    assert!(speed.get_enable());
+    assert_eq!(speed.get_payload(), Speed::High);
+    assert_eq!(speed.get_count(), 0x1f);
+    assert_eq!(Reg::<Speed>::TOTAL_BYTES, 1);
+    assert_eq!(speed.as_bytes(), &[0b1111_1101]);
+
+    let mut direction: Reg<Direction> = Reg::new();
+    direction.set_payload(Direction::Down);
+    assert!(!direction.get_enable());
+    assert_eq!(direction.get_payload(), Direction::Down);
+    assert_eq!(direction.get_payload_raw(), 1);
+
+    // The same bits read back as a different payload type.
+    let reinterpreted = Reg::<Direction>::from_le(speed.to_le::<u8>());
+    assert_eq!(reinterpreted.get_payload(), Direction::Left);
+    assert_eq!(
+        format!("{:?}", reinterpreted),
+        "Reg { enable: true, payload: Left, count: 31 }"
+    );
+}
@@ -0,0 +1,37 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use bit_field::*;
+
+#[bitfield]
+struct Descriptor {
+    valid: bool,
+    #[mbz]
+    reserved: B3,
+    kind: B4,
+    #[mbz]
+    #[bits = 8]
+    reserved_high: B8,
+}
+
+#[test]
+fn test_mbz() {
+    let mut desc = Descriptor::new();
+    desc.set_valid(true);
+    desc.set_kind(0xf);
+    assert_eq!(desc.validate(), Ok(()));
+
+    desc.set_reserved(0b100);
+    let err = desc.validate().unwrap_err();
+    assert_eq!(err.field(), "reserved");
+    assert_eq!(err.raw_val(), 0b100);
+    assert_eq!(
+        err.to_string(),
+        "reserved field reserved must be zero but is 0x4"
+    );
+
+    desc.set_reserved(0);
+    desc.set_reserved_high(1);
+    assert_eq!(desc.validate(), Err(MbzViolation::new("reserved_high", 1)));
+}
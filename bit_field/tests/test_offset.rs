@@ -0,0 +1,42 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use bit_field::*;
+
+#[bitfield]
+#[derive(Debug, PartialEq)]
+enum Kind {
+    Read = 0,
+    Write = 1,
+    Flush = 2,
+    Discard = 3,
+}
+
+#[bitfield]
+struct Request {
+    #[offset = 2]
+    kind: Kind,
+    #[offset = 8]
+    len: B12,
+    #[bits = 4]
+    flags: B4,
+}
+
+#[test]
+fn test_offset() {
+    assert_eq!(Request::TOTAL_BITS, 24);
+
+    let mut req = Request::new();
+    req.set_kind(Kind::Flush);
+    req.set_len(0xfff);
+    req.set_flags(0xf);
+    assert_eq!(req.get_kind(), Kind::Flush);
+    assert_eq!(req.get_len(), 0xfff);
+    assert_eq!(req.get_flags(), 0xf);
+
+    // The bits before and between the placed fields are left zero.
+    assert_eq!(req.get(0, 2), 0);
+    assert_eq!(req.get(4, 4), 0);
+    assert_eq!(req.as_bytes(), &[0b1000, 0xff, 0xff]);
+}
@@ -0,0 +1,100 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+// Code generated for a crate with strict lints must not trip them. This is checked when running
+// clippy on the tests.
+#![deny(clippy::all, clippy::pedantic, dead_code)]
+
+use bit_field::*;
+
+#[bitfield]
+#[derive(Debug, PartialEq)]
+enum Mode {
+    Off = 0,
+    On = 1,
+}
+
+#[bitfield]
+#[bits = 2]
+#[derive(Debug, PartialEq)]
+enum Speed {
+    Slow = 0,
+    Fast = 1,
+}
+
+#[bitfield]
+#[bits = 2]
+#[derive(Debug, PartialEq)]
+enum Command {
+    Read = 0,
+    #[fallback]
+    Unknown(u8),
+}
+
+#[bitfield]
+#[bits = 6]
+#[derive(Debug, PartialEq)]
+struct Level(u8);
+
+#[bitfield]
+struct Register {
+    enable: bool,
+    mode: Mode,
+    speed: Speed,
+    command: Command,
+    #[mbz]
+    reserved: B2,
+    level: Level,
+    #[offset = 16]
+    count: B16,
+    #[bits = 64]
+    address: B64,
+}
+
+#[bitfield(track_init)]
+struct Tracked {
+    low: B8,
+    high: B8,
+}
+
+#[bitfield]
+struct Generic<E: BitFieldSpecifier> {
+    #[bits = 1]
+    payload: E,
+    rest: B7,
+}
+
+#[test]
+fn test_strict_lints() {
+    let mut reg = Register::new();
+    reg.set_enable(true);
+    reg.set_mode(Mode::On);
+    reg.set_speed(Speed::Fast);
+    reg.set_command(Command::Read);
+    reg.set_level(Level(0x3f));
+    reg.set_count_bytes(&[0x34, 0x12]);
+    reg.set_address(u64::MAX);
+    assert!(reg.get_enable());
+    assert_eq!(reg.get_mode(), Mode::On);
+    assert!(matches!(reg.get_speed(), Ok(Speed::Fast)));
+    assert_eq!(reg.get_command(), Command::Read);
+    assert_eq!(reg.get_level(), Level(0x3f));
+    assert_eq!(reg.get_count(), 0x1234);
+    assert_eq!(reg.get_address(), u64::MAX);
+    assert_eq!(reg.validate(), Ok(()));
+    assert!(reg.diff(&Register::new()).len() > 1);
+    assert_eq!(reg.swap_reserved(0), 0);
+    assert_eq!(reg.get_reserved_raw(), 0);
+
+    let mut tracked = Tracked::new();
+    tracked.set_low(1);
+    tracked.set_high(2);
+    assert_eq!(tracked.to_le::<u16>(), 0x0201);
+
+    let mut generic: Generic<Mode> = Generic::new();
+    generic.set_payload(Mode::On);
+    generic.set_rest(0);
+    assert_eq!(generic.get_payload(), Mode::On);
+    assert_eq!(format!("{:?}", Command::Unknown(3)), "Unknown(3)");
+}
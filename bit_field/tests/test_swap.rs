@@ -0,0 +1,34 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use bit_field::*;
+
+#[bitfield]
+#[derive(Debug, PartialEq)]
+enum Mode {
+    Idle = 0,
+    Run = 1,
+}
+
+#[bitfield]
+struct S {
+    a: B3,
+    b: B4,
+    mode: Mode,
+}
+
+#[test]
+fn test_swap() {
+    let mut s = S::new();
+    s.set_a(0b101);
+    s.set_b(9);
+
+    assert_eq!(s.swap_b(5), 9);
+    assert_eq!(s.get_b(), 5);
+    assert_eq!(s.swap_b(5), 5);
+    assert_eq!(s.get_a(), 0b101);
+
+    assert_eq!(s.swap_mode(Mode::Run), Mode::Idle);
+    assert_eq!(s.get_mode(), Mode::Run);
+}
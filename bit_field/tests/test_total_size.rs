@@ -0,0 +1,25 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use bit_field::*;
+
+#[bitfield]
+struct TwoBytes {
+    low: B3,
+    high: B13,
+}
+
+#[test]
+fn test_total_size() {
+    assert_eq!(TwoBytes::TOTAL_BITS, 16);
+    check_total_bits(TwoBytes::TOTAL_BITS);
+}
+
+// The generated check fails to compile with this message rather than panicking; calling it at run
+// time shows the message.
+#[test]
+#[should_panic(expected = "total bitfield size is 10 bits, which is not a multiple of 8")]
+fn test_total_size_message() {
+    check_total_bits(10);
+}
@@ -0,0 +1,51 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use bit_field::*;
+
+#[bitfield(track_init)]
+struct Register {
+    enable: bool,
+    mode: B7,
+    count: B16,
+    addr: B8,
+}
+
+#[test]
+fn written_fields_read_back() {
+    let mut reg = Register::new();
+    reg.set_enable(true);
+    reg.set_count(0x1234);
+    reg.set_addr(0xab);
+    assert!(reg.get_enable());
+    assert_eq!(reg.get_mode(), 0);
+    assert_eq!(reg.get_count(), 0x1234);
+    assert_eq!(reg.get_count_bytes(), &[0x34, 0x12]);
+    assert_eq!(reg.get_addr_raw(), 0xab);
+}
+
+#[test]
+fn from_le_counts_as_written() {
+    let reg = Register::from_le(0xab12_3401u32);
+    assert!(reg.get_enable());
+    assert_eq!(reg.get_mode(), 0);
+    assert_eq!(reg.get_count(), 0x1234);
+    assert_eq!(reg.get_addr(), 0xab);
+}
+
+#[test]
+fn set_bytes_counts_as_written() {
+    let mut reg = Register::new();
+    reg.set_count_bytes(&[0x34, 0x12]);
+    assert_eq!(reg.get_count(), 0x1234);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "bitfield field `count` read before it was written")]
+fn read_untouched_field() {
+    let mut reg = Register::new();
+    reg.set_mode(3);
+    reg.get_count();
+}
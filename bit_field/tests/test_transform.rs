@@ -0,0 +1,46 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use bit_field::*;
+
+fn decode_log2(log2: u16) -> u16 {
+    1 << log2
+}
+
+fn encode_log2(val: u16) -> u16 {
+    assert!(val.is_power_of_two());
+    val.trailing_zeros() as u16
+}
+
+mod encoding {
+    pub fn negate(val: u8) -> u8 {
+        !val & 0xf
+    }
+}
+
+#[bitfield]
+struct QueueConfig {
+    #[transform(get = "decode_log2", set = "encode_log2")]
+    size: B12,
+    #[transform(set = "encoding::negate")]
+    #[transform(get = "encoding::negate")]
+    inverted: B4,
+}
+
+#[test]
+fn test_transform() {
+    let mut config = QueueConfig::new();
+    for size in [1, 2, 256, 0x800] {
+        config.set_size(size);
+        assert_eq!(config.get_size(), size);
+        assert_eq!(config.get_size_raw(), u64::from(size.trailing_zeros()));
+    }
+
+    assert_eq!(config.swap_size(16), 0x800);
+    assert_eq!(config.get_size_raw(), 4);
+
+    config.set_inverted(0x3);
+    assert_eq!(config.get_inverted(), 0x3);
+    assert_eq!(config.get_inverted_raw(), 0xc);
+}
@@ -0,0 +1,50 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use bit_field::*;
+
+#[bitfield]
+#[bits = 3]
+#[derive(Debug, PartialEq, Eq)]
+enum Opcode {
+    Nop = 0,
+    Load = 1,
+    Store = 2,
+}
+
+#[bitfield]
+#[bits = 2]
+#[derive(Debug, PartialEq, Eq)]
+enum Flag {
+    Clear = 0,
+    #[fallback]
+    Other(u8),
+}
+
+#[bitfield]
+struct Instruction {
+    opcode: Opcode,
+    flag: Flag,
+    reg: B3,
+    imm: B8,
+}
+
+// Bits 0 to 2 hold the opcode, 3 and 4 the flag, 5 to 7 the register.
+#[test]
+fn test_try_from() {
+    let insn = Instruction::try_from([0b1111_0010, 0x5a]).unwrap();
+    assert_eq!(insn.get_opcode().unwrap(), Opcode::Store);
+    assert_eq!(insn.get_flag(), Flag::Other(0b10));
+    assert_eq!(insn.get_reg(), 0b111);
+    assert_eq!(insn.get_imm(), 0x5a);
+
+    let err = Instruction::try_from([0b0000_0101, 0]).unwrap_err();
+    assert_eq!(err, InvalidFieldValue::new("opcode", 0b101));
+    assert_eq!(err.to_string(), "field opcode has an invalid value 0x5");
+
+    // The unchecked conversion keeps the bits as they are.
+    let insn = Instruction::from_bytes([0b0000_0101, 0]);
+    assert_eq!(insn.get_opcode_raw(), 0b101);
+    assert!(insn.get_opcode().is_err());
+}
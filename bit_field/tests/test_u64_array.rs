@@ -0,0 +1,49 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use bit_field::*;
+
+#[bitfield]
+#[derive(PartialEq)]
+struct Descriptor {
+    addr: B64,
+    len: B32,
+    flags: B16,
+    id: B16,
+}
+
+#[bitfield]
+#[derive(PartialEq)]
+struct TwelveBytes {
+    low: B64,
+    high: B32,
+}
+
+#[test]
+fn test_u64_array() {
+    let mut desc = Descriptor::new();
+    desc.set_addr(0x1122_3344_5566_7788);
+    desc.set_len(0x1000);
+    desc.set_flags(0x3);
+    desc.set_id(0xabcd);
+
+    let words = desc.to_le_u64_array();
+    assert_eq!(words, [0x1122_3344_5566_7788, 0xabcd_0003_0000_1000]);
+    assert_eq!(Descriptor::from_le_u64_array(words), desc);
+}
+
+#[test]
+fn test_u64_array_tail() {
+    let mut val = TwelveBytes::new();
+    val.set_low(u64::MAX);
+    val.set_high(0x89ab_cdef);
+
+    let words = val.to_le_u64_array();
+    assert_eq!(words, [u64::MAX, 0x89ab_cdef]);
+    // Bytes past the end of the struct are dropped.
+    assert_eq!(
+        TwelveBytes::from_le_u64_array([u64::MAX, 0xffff_ffff_89ab_cdef]),
+        val
+    );
+}
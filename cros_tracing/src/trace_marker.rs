@@ -236,7 +236,8 @@ setup_trace_marker!(
     (USB, true),
     (gpu_display, true),
     (VirtioBlk, true),
-    (VirtioScsi, true)
+    (VirtioScsi, true),
+    (VirtioMmio, true)
 );
 
 /// Platform-specific implementation of the `trace_simple_print!` macro. If tracing
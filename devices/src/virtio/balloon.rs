@@ -1547,6 +1547,10 @@ impl VirtioDevice for Balloon {
         QUEUE_SIZES
     }
 
+    fn config_len(&self) -> Option<usize> {
+        Some(std::mem::size_of::<virtio_balloon_config>())
+    }
+
     fn read_config(&self, offset: u64, data: &mut [u8]) {
         copy_config(data, 0, self.get_config().as_bytes(), offset);
     }
@@ -1107,6 +1107,10 @@ impl VirtioDevice for BlockAsync {
         &self.queue_sizes
     }
 
+    fn config_len(&self) -> Option<usize> {
+        Some(size_of::<virtio_blk_config>())
+    }
+
     fn read_config(&self, offset: u64, data: &mut [u8]) {
         let config_space = {
             let disk_size = self.disk_size.load(Ordering::Acquire);
@@ -495,6 +495,10 @@ impl VirtioDevice for AsyncConsole {
         QUEUE_SIZES
     }
 
+    fn config_len(&self) -> Option<usize> {
+        Some(std::mem::size_of::<virtio_console_config>())
+    }
+
     fn read_config(&self, offset: u64, data: &mut [u8]) {
         let config = virtio_console_config {
             max_nr_ports: 1.into(),
@@ -1714,6 +1714,10 @@ impl VirtioDevice for Gpu {
         let _ = value;
     }
 
+    fn config_len(&self) -> Option<usize> {
+        Some(std::mem::size_of::<virtio_gpu_config>())
+    }
+
     fn read_config(&self, offset: u64, data: &mut [u8]) {
         copy_config(data, 0, self.get_config().as_bytes(), offset);
     }
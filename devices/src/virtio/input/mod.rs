@@ -562,6 +562,10 @@ where
         QUEUE_SIZES
     }
 
+    fn config_len(&self) -> Option<usize> {
+        Some(std::mem::size_of::<virtio_input_config>())
+    }
+
     fn read_config(&self, offset: u64, data: &mut [u8]) {
         self.config.read(offset as usize, data);
     }
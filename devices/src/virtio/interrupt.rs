@@ -2,6 +2,7 @@
 // Use of this source code is governed by a BSD-style license that can be
 // found in the LICENSE file.
 
+use std::sync::atomic::AtomicU64;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
@@ -34,6 +35,8 @@ enum Transport {
     },
     Mmio {
         irq_evt_edge: IrqEdgeEvent,
+        // Interrupts of the queues with their own event, indexed by vector.
+        queue_irq_evts: Vec<IrqEdgeEvent>,
     },
     VhostUser {
         call_evt: Event,
@@ -41,8 +44,58 @@ enum Transport {
     },
 }
 
+/// The virtio interrupt status register (ISR) shared between a device and its transport.
+#[derive(Debug, Default)]
+pub struct InterruptStatus(AtomicUsize);
+
+impl InterruptStatus {
+    /// Buffers have been placed in a used ring.
+    pub const VRING: u8 = INTERRUPT_STATUS_USED_RING as u8;
+    /// The device configuration space has changed.
+    pub const CONFIG: u8 = INTERRUPT_STATUS_CONFIG_CHANGED as u8;
+
+    const ALL: u8 = Self::VRING | Self::CONFIG;
+
+    fn from_bits(bits: usize) -> Self {
+        InterruptStatus(AtomicUsize::new(bits))
+    }
+
+    /// Sets the used ring bit. Returns `true` if no interrupt was pending before this call.
+    pub fn signal_used(&self) -> bool {
+        self.raise(Self::VRING)
+    }
+
+    /// Sets the config changed bit. Returns `true` if no interrupt was pending before this call.
+    pub fn signal_config(&self) -> bool {
+        self.raise(Self::CONFIG)
+    }
+
+    /// Sets `bits` in the status. Returns `true` if no interrupt was pending before this call.
+    fn raise(&self, bits: u8) -> bool {
+        self.0.fetch_or(bits as usize, Ordering::SeqCst) == 0
+    }
+
+    /// Clears the bits of `bits` that the driver is acknowledging. Unknown bits are ignored.
+    pub fn ack(&self, bits: u8) {
+        self.0
+            .fetch_and(!((bits & Self::ALL) as usize), Ordering::SeqCst);
+    }
+
+    /// Reads the current value of the status.
+    pub fn read(&self) -> u8 {
+        self.0.load(Ordering::SeqCst) as u8
+    }
+
+    /// Reads the current value of the status and resets it to 0.
+    pub fn take(&self) -> u8 {
+        self.0.swap(0, Ordering::SeqCst) as u8
+    }
+}
+
 struct InterruptInner {
-    interrupt_status: AtomicUsize,
+    interrupt_status: InterruptStatus,
+    // Number of times the device signaled a configuration change.
+    config_changes: AtomicU64,
     transport: Transport,
     async_intr_status: bool,
     #[cfg(target_arch = "x86_64")]
@@ -58,10 +111,7 @@ impl InterruptInner {
         // Don't need to inject the interrupt if the guest hasn't processed it.
         // In hypervisors where interrupt_status is updated asynchronously, inject the
         // interrupt even if the previous interrupt appears to be already pending.
-        self.interrupt_status
-            .fetch_or(interrupt_status_mask as usize, Ordering::SeqCst)
-            == 0
-            || self.async_intr_status
+        self.interrupt_status.raise(interrupt_status_mask as u8) || self.async_intr_status
     }
 }
 
@@ -104,7 +154,15 @@ impl Interrupt {
                     pci.irq_evt_lvl.trigger().unwrap();
                 }
             }
-            Transport::Mmio { irq_evt_edge } => {
+            Transport::Mmio {
+                irq_evt_edge,
+                queue_irq_evts,
+            } => {
+                // Like MSI-X, a queue's own interrupt doesn't go through the interrupt status.
+                if let Some(queue_irq_evt) = queue_irq_evts.get(vector as usize) {
+                    queue_irq_evt.trigger().unwrap();
+                    return;
+                }
                 if self.inner.update_interrupt_status(interrupt_status_mask) {
                     irq_evt_edge.trigger().unwrap();
                 }
@@ -125,6 +183,7 @@ impl Interrupt {
 
     /// Notify the driver that the device configuration has changed.
     pub fn signal_config_changed(&self) {
+        self.inner.config_changes.fetch_add(1, Ordering::SeqCst);
         match &self.inner.as_ref().transport {
             Transport::Pci { pci } => {
                 self.signal(pci.config_msix_vector, INTERRUPT_STATUS_CONFIG_CHANGED)
@@ -139,6 +198,11 @@ impl Interrupt {
         }
     }
 
+    /// Returns how many times `signal_config_changed` was called on this interrupt or its clones.
+    pub fn config_change_count(&self) -> u64 {
+        self.inner.config_changes.load(Ordering::SeqCst)
+    }
+
     /// Get the event to signal resampling is needed if it exists.
     pub fn get_resample_evt(&self) -> Option<&Event> {
         match &self.inner.as_ref().transport {
@@ -150,7 +214,7 @@ impl Interrupt {
     /// Reads the status and writes to the interrupt event. Doesn't read the resample event, it
     /// assumes the resample has been requested.
     pub fn do_interrupt_resample(&self) {
-        if self.inner.interrupt_status.load(Ordering::SeqCst) != 0 {
+        if self.inner.interrupt_status.read() != 0 {
             match &self.inner.as_ref().transport {
                 Transport::Pci { pci } => pci.irq_evt_lvl.trigger().unwrap(),
                 _ => panic!("do_interrupt_resample() not supported"),
@@ -168,7 +232,8 @@ impl Interrupt {
     ) -> Interrupt {
         Interrupt {
             inner: Arc::new(InterruptInner {
-                interrupt_status: AtomicUsize::new(0),
+                interrupt_status: InterruptStatus::default(),
+                config_changes: AtomicU64::new(0),
                 async_intr_status: false,
                 transport: Transport::Pci {
                     pci: TransportPci {
@@ -195,7 +260,8 @@ impl Interrupt {
     ) -> Interrupt {
         Interrupt {
             inner: Arc::new(InterruptInner {
-                interrupt_status: AtomicUsize::new(snapshot.interrupt_status),
+                interrupt_status: InterruptStatus::from_bits(snapshot.interrupt_status),
+                config_changes: AtomicU64::new(0),
                 async_intr_status: false,
                 transport: Transport::Pci {
                     pci: TransportPci {
@@ -211,10 +277,24 @@ impl Interrupt {
     }
 
     pub fn new_mmio(irq_evt_edge: IrqEdgeEvent, async_intr_status: bool) -> Interrupt {
+        Self::new_mmio_with_queue_irqs(irq_evt_edge, Vec::new(), async_intr_status)
+    }
+
+    /// Create an MMIO `Interrupt` that signals used buffers of queues with vector `i` on
+    /// `queue_irq_evts[i]`. Other queues and configuration changes use `irq_evt_edge`.
+    pub fn new_mmio_with_queue_irqs(
+        irq_evt_edge: IrqEdgeEvent,
+        queue_irq_evts: Vec<IrqEdgeEvent>,
+        async_intr_status: bool,
+    ) -> Interrupt {
         Interrupt {
             inner: Arc::new(InterruptInner {
-                interrupt_status: AtomicUsize::new(0),
-                transport: Transport::Mmio { irq_evt_edge },
+                interrupt_status: InterruptStatus::default(),
+                config_changes: AtomicU64::new(0),
+                transport: Transport::Mmio {
+                    irq_evt_edge,
+                    queue_irq_evts,
+                },
                 async_intr_status,
                 #[cfg(target_arch = "x86_64")]
                 wakeup_event: None,
@@ -230,7 +310,8 @@ impl Interrupt {
     ) -> Interrupt {
         Interrupt {
             inner: Arc::new(InterruptInner {
-                interrupt_status: AtomicUsize::new(0),
+                interrupt_status: InterruptStatus::default(),
+                config_changes: AtomicU64::new(0),
                 transport: Transport::VhostUser {
                     call_evt,
                     signal_config_changed_fn,
@@ -277,7 +358,7 @@ impl Interrupt {
     pub fn get_interrupt_evt(&self) -> &Event {
         match &self.inner.as_ref().transport {
             Transport::Pci { pci } => pci.irq_evt_lvl.get_trigger(),
-            Transport::Mmio { irq_evt_edge } => irq_evt_edge.get_trigger(),
+            Transport::Mmio { irq_evt_edge, .. } => irq_evt_edge.get_trigger(),
             Transport::VhostUser { call_evt, .. } => call_evt,
         }
     }
@@ -303,25 +384,23 @@ impl Interrupt {
 
     /// Reads the current value of the interrupt status.
     pub fn read_interrupt_status(&self) -> u8 {
-        self.inner.interrupt_status.load(Ordering::SeqCst) as u8
+        self.inner.interrupt_status.read()
     }
 
     /// Reads the current value of the interrupt status and resets it to 0.
     pub fn read_and_reset_interrupt_status(&self) -> u8 {
-        self.inner.interrupt_status.swap(0, Ordering::SeqCst) as u8
+        self.inner.interrupt_status.take()
     }
 
     /// Clear the bits set in `mask` in the interrupt status.
     pub fn clear_interrupt_status_bits(&self, mask: u8) {
-        self.inner
-            .interrupt_status
-            .fetch_and(!(mask as usize), Ordering::SeqCst);
+        self.inner.interrupt_status.ack(mask);
     }
 
     /// Snapshot internal state. Can be restored with with `Interrupt::new_from_snapshot`.
     pub fn snapshot(&self) -> InterruptSnapshot {
         InterruptSnapshot {
-            interrupt_status: self.inner.interrupt_status.load(Ordering::SeqCst),
+            interrupt_status: self.inner.interrupt_status.read().into(),
         }
     }
 
@@ -332,3 +411,25 @@ impl Interrupt {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interrupt_status_signal_and_ack() {
+        let status = InterruptStatus::default();
+        assert!(status.signal_used());
+        assert!(!status.signal_config());
+        assert_eq!(
+            status.read(),
+            InterruptStatus::VRING | InterruptStatus::CONFIG
+        );
+
+        status.ack(InterruptStatus::VRING | 0x80);
+        assert_eq!(status.read(), InterruptStatus::CONFIG);
+
+        assert_eq!(status.take(), InterruptStatus::CONFIG);
+        assert_eq!(status.read(), 0);
+    }
+}
@@ -791,6 +791,10 @@ impl VirtioDevice for Iommu {
         self.avail_features
     }
 
+    fn config_len(&self) -> Option<usize> {
+        Some(size_of::<virtio_iommu_config>())
+    }
+
     fn read_config(&self, offset: u64, data: &mut [u8]) {
         let mut config: Vec<u8> = Vec::new();
         config.extend_from_slice(self.config.as_bytes());
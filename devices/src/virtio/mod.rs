@@ -28,6 +28,7 @@ mod virtio_device;
 mod virtio_mmio_device;
 mod virtio_pci_common_config;
 mod virtio_pci_device;
+mod virtio_transport;
 
 pub mod block;
 pub mod console;
@@ -73,6 +74,7 @@ pub use self::gpu::GpuParameters;
 pub use self::gpu::GpuWsi;
 pub use self::interrupt::Interrupt;
 pub use self::interrupt::InterruptSnapshot;
+pub use self::interrupt::InterruptStatus;
 pub use self::iommu::ipc_memory_mapper;
 pub use self::iommu::memory_mapper;
 pub use self::iommu::Iommu;
@@ -100,15 +102,21 @@ pub use self::tpm::TpmBackend;
 pub use self::vhost_user_frontend::VhostUserFrontend;
 #[cfg(any(feature = "video-decoder", feature = "video-encoder"))]
 pub use self::video::VideoDevice;
+pub use self::virtio_device::AsAny;
 pub use self::virtio_device::SharedMemoryMapper;
 pub use self::virtio_device::SharedMemoryRegion;
 pub use self::virtio_device::VirtioDevice;
 pub use self::virtio_device::VirtioTransportType;
+pub use self::virtio_mmio_device::MemoryAccessTracker;
 pub use self::virtio_mmio_device::VirtioMmioDevice;
+pub use self::virtio_mmio_device::VirtioMmioIdentity;
+pub use self::virtio_mmio_device::VirtioMmioQueueInfo;
+pub use self::virtio_mmio_device::VirtioMmioStats;
 pub use self::virtio_pci_device::PciCapabilityType;
 pub use self::virtio_pci_device::VirtioPciCap;
 pub use self::virtio_pci_device::VirtioPciDevice;
 pub use self::virtio_pci_device::VirtioPciShmCap;
+pub use self::virtio_transport::VirtioTransport;
 #[cfg(feature = "pvclock")]
 pub use self::DeviceType::Pvclock;
 
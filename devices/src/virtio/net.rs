@@ -655,6 +655,10 @@ where
         }
     }
 
+    fn config_len(&self) -> Option<usize> {
+        Some(std::mem::size_of::<VirtioNetConfig>())
+    }
+
     fn read_config(&self, offset: u64, data: &mut [u8]) {
         let vq_pairs = self.queue_sizes.len() / 2;
         let config_space = build_config(vq_pairs as u16, self.mtu, self.guest_mac);
@@ -198,6 +198,10 @@ impl VirtioDevice for P9 {
         self.acked_features |= v;
     }
 
+    fn config_len(&self) -> Option<usize> {
+        Some(self.config.len())
+    }
+
     fn read_config(&self, offset: u64, data: &mut [u8]) {
         copy_config(data, 0, self.config.as_slice(), offset);
     }
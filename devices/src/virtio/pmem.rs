@@ -284,6 +284,10 @@ impl VirtioDevice for Pmem {
         self.base_features
     }
 
+    fn config_len(&self) -> Option<usize> {
+        Some(std::mem::size_of::<virtio_pmem_config>())
+    }
+
     fn read_config(&self, offset: u64, data: &mut [u8]) {
         let config = virtio_pmem_config {
             start_address: Le64::from(self.mapping_address.offset()),
@@ -878,6 +878,10 @@ impl VirtioDevice for PvClock {
         self.state.acked_features |= value;
     }
 
+    fn config_len(&self) -> Option<usize> {
+        Some(size_of::<virtio_pvclock_config>())
+    }
+
     fn read_config(&self, offset: u64, data: &mut [u8]) {
         copy_config(data, 0, self.get_config().as_bytes(), offset);
     }
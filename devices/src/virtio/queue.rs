@@ -244,6 +244,11 @@ impl QueueConfig {
         self.acked_features
     }
 
+    /// Adds `features` to those the driver may acknowledge on this queue.
+    pub fn add_features(&mut self, features: u64) {
+        self.features |= features;
+    }
+
     /// Acknowledges that this set of features should be enabled on this queue.
     pub fn ack_features(&mut self, features: u64) {
         self.acked_features |= features & self.features;
@@ -259,6 +264,16 @@ impl QueueConfig {
         self.ready = enable;
     }
 
+    /// Returns the guest memory regions of the rings as programmed by the driver, as
+    /// (address, length) pairs.
+    pub fn ring_regions(&self) -> Vec<(GuestAddress, usize)> {
+        if ((self.acked_features >> VIRTIO_F_RING_PACKED) & 1) != 0 {
+            PackedQueue::area_sizes(self.size, self.desc_table, self.avail_ring, self.used_ring)
+        } else {
+            SplitQueue::ring_sizes(self.size, self.desc_table, self.avail_ring, self.used_ring)
+        }
+    }
+
     /// Convert the queue configuration into an active queue.
     pub fn activate(&mut self, mem: &GuestMemory, event: Event) -> Result<Queue> {
         if !self.ready {
@@ -284,6 +299,14 @@ impl QueueConfig {
         Ok(queue)
     }
 
+    /// Allows the queue to be activated again, keeping its configuration.
+    ///
+    /// Only valid if the `Queue` returned by `activate` was dropped without being used, e.g.
+    /// because the device failed to activate.
+    pub fn deactivate(&mut self) {
+        self.activated = false;
+    }
+
     /// Reset queue to a clean state
     pub fn reset(&mut self) {
         self.activated = false;
@@ -197,7 +197,7 @@ impl PackedQueue {
         &self.event
     }
 
-    fn area_sizes(
+    pub(super) fn area_sizes(
         queue_size: u16,
         desc_table: GuestAddress,
         driver_area: GuestAddress,
@@ -165,7 +165,7 @@ impl SplitQueue {
         index.0 & self.size.wrapping_sub(1)
     }
 
-    fn ring_sizes(
+    pub(super) fn ring_sizes(
         queue_size: u16,
         desc_table: GuestAddress,
         avail_ring: GuestAddress,
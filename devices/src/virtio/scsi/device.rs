@@ -605,6 +605,10 @@ impl VirtioDevice for Controller {
         &self.queue_sizes
     }
 
+    fn config_len(&self) -> Option<usize> {
+        Some(std::mem::size_of::<virtio_scsi_config>())
+    }
+
     fn read_config(&self, offset: u64, data: &mut [u8]) {
         let config_space = self.build_config_space();
         copy_config(data, 0, config_space.as_bytes(), offset);
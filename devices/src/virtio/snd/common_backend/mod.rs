@@ -432,6 +432,10 @@ impl VirtioDevice for VirtioSnd {
         self.acked_features |= v;
     }
 
+    fn config_len(&self) -> Option<usize> {
+        Some(std::mem::size_of::<virtio_snd_config>())
+    }
+
     fn read_config(&self, offset: u64, data: &mut [u8]) {
         copy_config(data, 0, self.cfg.as_bytes(), offset)
     }
@@ -30,6 +30,7 @@ use super::Result;
 use crate::pci::MsixStatus;
 use crate::virtio::copy_config;
 use crate::virtio::net::build_config;
+use crate::virtio::net::VirtioNetConfig;
 use crate::virtio::DeviceType;
 use crate::virtio::Interrupt;
 use crate::virtio::Queue;
@@ -177,6 +178,10 @@ where
         self.acked_features |= v;
     }
 
+    fn config_len(&self) -> Option<usize> {
+        Some(mem::size_of::<VirtioNetConfig>())
+    }
+
     fn read_config(&self, offset: u64, data: &mut [u8]) {
         let vq_pairs = QUEUE_SIZES.len() / 2;
         // VIRTIO_NET_F_MTU is not set.
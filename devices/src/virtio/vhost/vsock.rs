@@ -150,6 +150,10 @@ impl VirtioDevice for Vsock {
         self.avail_features
     }
 
+    fn config_len(&self) -> Option<usize> {
+        Some(std::mem::size_of::<Le64>())
+    }
+
     fn read_config(&self, offset: u64, data: &mut [u8]) {
         let cid = Le64::from(self.cid);
         copy_config(data, 0, cid.as_bytes(), offset);
@@ -206,6 +206,10 @@ impl VirtioDevice for VideoDevice {
         self.base_features | backend_supported_virtio_features(self.backend)
     }
 
+    fn config_len(&self) -> Option<usize> {
+        Some(std::mem::size_of::<virtio_video_config>())
+    }
+
     fn read_config(&self, offset: u64, data: &mut [u8]) {
         let mut cfg = build_config(self.backend);
         copy_config(data, 0, cfg.as_bytes_mut(), offset);
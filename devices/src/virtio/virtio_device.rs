@@ -2,6 +2,7 @@
 // Use of this source code is governed by a BSD-style license that can be
 // found in the LICENSE file.
 
+use std::any::Any;
 use std::collections::BTreeMap;
 use std::sync::Arc;
 
@@ -60,6 +61,24 @@ pub trait SharedMemoryMapper: Send {
     }
 }
 
+/// Allows a `dyn VirtioDevice` to be downcast to its concrete type.
+///
+/// This is implemented for every `'static` type, so virtio devices get it for free.
+pub trait AsAny {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
 /// Trait for virtio devices to be driven by a virtio transport.
 ///
 /// The lifecycle of a virtio device is to be moved to a virtio transport, which will then query the
@@ -67,7 +86,7 @@ pub trait SharedMemoryMapper: Send {
 /// and all the events, memory, and queues for device operation will be moved into the device.
 /// Optionally, a virtio device can implement device reset in which it returns said resources and
 /// resets its internal.
-pub trait VirtioDevice: Send {
+pub trait VirtioDevice: Send + AsAny {
     /// Returns a label suitable for debug output.
     fn debug_label(&self) -> String {
         format!("virtio-{}", self.device_type())
@@ -98,6 +117,21 @@ pub trait VirtioDevice: Send {
         let _ = value;
     }
 
+    /// Tells the device which of the features offered by its transport on top of `features` the
+    /// driver acknowledged, e.g. ring features that change what the device may do. Called on
+    /// activation, before `activate`.
+    fn set_transport_features(&mut self, features: u64) {
+        let _ = features;
+    }
+
+    /// The length in bytes of this device's configuration space, if known.
+    ///
+    /// When this returns `Some`, transports will not forward configuration space accesses beyond
+    /// this length to `read_config`.
+    fn config_len(&self) -> Option<usize> {
+        None
+    }
+
     /// Reads this device configuration space at `offset`.
     fn read_config(&self, offset: u64, data: &mut [u8]) {
         let _ = offset;
@@ -126,6 +160,25 @@ pub trait VirtioDevice: Send {
         Err(anyhow!("reset not implemented for {}", self.debug_label()))
     }
 
+    /// Receives the data the driver wrote along with a notification of the queue at
+    /// `queue_index`, when VIRTIO_F_NOTIFICATION_DATA was negotiated. Called for every such
+    /// notification before the queue's event is signaled.
+    fn queue_notified(&mut self, queue_index: usize, notify_data: u32) {
+        let _ = queue_index;
+        let _ = notify_data;
+    }
+
+    /// Stops using the queue at `queue_index` when the driver resets just that queue after
+    /// activation, handing it back to the transport. An `Err` value is returned if the device
+    /// can't reset a single queue, in which case the queue stays in use.
+    fn reset_queue(&mut self, queue_index: usize) -> Result<Option<Queue>> {
+        let _ = queue_index;
+        Err(anyhow!(
+            "queue reset not implemented for {}",
+            self.debug_label()
+        ))
+    }
+
     /// Returns any additional BAR configuration required by the device.
     fn get_device_bars(&mut self, _address: PciAddress) -> Vec<PciBarConfiguration> {
         Vec::new()
@@ -3,6 +3,11 @@
 // found in the LICENSE file.
 
 use std::collections::BTreeMap;
+#[cfg(debug_assertions)]
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 
 use acpi_tables::aml;
 use acpi_tables::aml::Aml;
@@ -10,6 +15,7 @@ use acpi_tables::aml::Aml;
 use acpi_tables::sdt::SDT;
 use anyhow::anyhow;
 use anyhow::Context;
+use base::debug;
 use base::error;
 use base::pagesize;
 use base::warn;
@@ -20,13 +26,16 @@ use base::Result;
 use hypervisor::Datamatch;
 use resources::AllocOptions;
 use resources::SystemAllocator;
+use sync::Mutex;
 use virtio_sys::virtio_config::VIRTIO_CONFIG_S_ACKNOWLEDGE;
 use virtio_sys::virtio_config::VIRTIO_CONFIG_S_DRIVER;
 use virtio_sys::virtio_config::VIRTIO_CONFIG_S_DRIVER_OK;
 use virtio_sys::virtio_config::VIRTIO_CONFIG_S_FAILED;
 use virtio_sys::virtio_config::VIRTIO_CONFIG_S_FEATURES_OK;
 use virtio_sys::virtio_config::VIRTIO_CONFIG_S_NEEDS_RESET;
+use virtio_sys::virtio_config::VIRTIO_F_NOTIFICATION_DATA;
 use virtio_sys::virtio_mmio::*;
+use vm_memory::GuestAddress;
 use vm_memory::GuestMemory;
 
 use super::*;
@@ -42,6 +51,165 @@ const VIRT_MAGIC: u32 = 0x74726976; /* 'virt' */
 const VIRT_VERSION: u8 = 2;
 const VIRT_VENDOR: u32 = 0x4D565243; /* 'CRVM' */
 const VIRTIO_MMIO_REGION_SZ: u64 = 0x200;
+// Feature bits are exposed 32 at a time, and devices have 64 of them.
+const NUM_FEATURE_WORDS: u32 = 2;
+
+/// Number of guest accesses to a `VirtioMmioDevice`, split between the control registers and the
+/// device-specific configuration space.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VirtioMmioStats {
+    pub control_reads: u64,
+    pub control_writes: u64,
+    pub config_reads: u64,
+    pub config_writes: u64,
+}
+
+/// The identification registers of a `VirtioMmioDevice`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VirtioMmioIdentity {
+    pub magic: u32,
+    pub version: u32,
+    pub device_id: u32,
+    pub vendor_id: u32,
+}
+
+/// The configuration of a queue as programmed by the driver.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VirtioMmioQueueInfo {
+    pub desc_table: GuestAddress,
+    pub avail_ring: GuestAddress,
+    pub used_ring: GuestAddress,
+    pub size: u16,
+    pub ready: bool,
+}
+
+/// Records the guest memory regions a `VirtioMmioDevice` may access, as candidates for dirty page
+/// tracking during live migration. Only the rings programmed by the driver are recorded, when the
+/// device is activated, not the buffers they point to.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryAccessTracker {
+    regions: Vec<(GuestAddress, usize)>,
+}
+
+impl MemoryAccessTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that the `len` bytes at `addr` may be accessed. Regions already recorded are not
+    /// added again.
+    pub fn record(&mut self, addr: GuestAddress, len: usize) {
+        if !self.regions.contains(&(addr, len)) {
+            self.regions.push((addr, len));
+        }
+    }
+
+    /// Returns the recorded regions as (address, length) pairs, in the order they were recorded.
+    pub fn regions(&self) -> &[(GuestAddress, usize)] {
+        &self.regions
+    }
+}
+
+const DRIVER_STATUS_FLAGS: &[(u32, &str)] = &[
+    (VIRTIO_CONFIG_S_ACKNOWLEDGE, "ACKNOWLEDGE"),
+    (VIRTIO_CONFIG_S_DRIVER, "DRIVER"),
+    (VIRTIO_CONFIG_S_DRIVER_OK, "DRIVER_OK"),
+    (VIRTIO_CONFIG_S_FEATURES_OK, "FEATURES_OK"),
+    (VIRTIO_CONFIG_S_NEEDS_RESET, "NEEDS_RESET"),
+    (VIRTIO_CONFIG_S_FAILED, "FAILED"),
+];
+
+/// Returns the name given by the virtio spec to the register at `offset`.
+fn reg_name(offset: u64) -> &'static str {
+    if offset >= VIRTIO_MMIO_CONFIG as u64 {
+        return "Config";
+    }
+    match offset as u32 {
+        VIRTIO_MMIO_MAGIC_VALUE => "MagicValue",
+        VIRTIO_MMIO_VERSION => "Version",
+        VIRTIO_MMIO_DEVICE_ID => "DeviceID",
+        VIRTIO_MMIO_VENDOR_ID => "VendorID",
+        VIRTIO_MMIO_DEVICE_FEATURES => "DeviceFeatures",
+        VIRTIO_MMIO_DEVICE_FEATURES_SEL => "DeviceFeaturesSel",
+        VIRTIO_MMIO_DRIVER_FEATURES => "DriverFeatures",
+        VIRTIO_MMIO_DRIVER_FEATURES_SEL => "DriverFeaturesSel",
+        VIRTIO_MMIO_GUEST_PAGE_SIZE => "GuestPageSize",
+        VIRTIO_MMIO_QUEUE_SEL => "QueueSel",
+        VIRTIO_MMIO_QUEUE_NUM_MAX => "QueueNumMax",
+        VIRTIO_MMIO_QUEUE_NUM => "QueueNum",
+        VIRTIO_MMIO_QUEUE_ALIGN => "QueueAlign",
+        VIRTIO_MMIO_QUEUE_PFN => "QueuePFN",
+        VIRTIO_MMIO_QUEUE_READY => "QueueReady",
+        VIRTIO_MMIO_QUEUE_NOTIFY => "QueueNotify",
+        VIRTIO_MMIO_INTERRUPT_STATUS => "InterruptStatus",
+        VIRTIO_MMIO_INTERRUPT_ACK => "InterruptACK",
+        VIRTIO_MMIO_STATUS => "Status",
+        VIRTIO_MMIO_QUEUE_DESC_LOW => "QueueDescLow",
+        VIRTIO_MMIO_QUEUE_DESC_HIGH => "QueueDescHigh",
+        VIRTIO_MMIO_QUEUE_AVAIL_LOW => "QueueDriverLow",
+        VIRTIO_MMIO_QUEUE_AVAIL_HIGH => "QueueDriverHigh",
+        VIRTIO_MMIO_QUEUE_USED_LOW => "QueueDeviceLow",
+        VIRTIO_MMIO_QUEUE_USED_HIGH => "QueueDeviceHigh",
+        VIRTIO_MMIO_SHM_SEL => "SHMSel",
+        VIRTIO_MMIO_SHM_LEN_LOW => "SHMLenLow",
+        VIRTIO_MMIO_SHM_LEN_HIGH => "SHMLenHigh",
+        VIRTIO_MMIO_SHM_BASE_LOW => "SHMBaseLow",
+        VIRTIO_MMIO_SHM_BASE_HIGH => "SHMBaseHigh",
+        VIRTIO_MMIO_CONFIG_GENERATION => "ConfigGeneration",
+        _ => "unknown control register",
+    }
+}
+
+/// Returns true if the register at `offset` can only be read by the driver.
+fn is_read_only_reg(offset: u64) -> bool {
+    if offset >= VIRTIO_MMIO_CONFIG as u64 {
+        return false;
+    }
+    matches!(
+        offset as u32,
+        VIRTIO_MMIO_MAGIC_VALUE
+            | VIRTIO_MMIO_VERSION
+            | VIRTIO_MMIO_DEVICE_ID
+            | VIRTIO_MMIO_VENDOR_ID
+            | VIRTIO_MMIO_DEVICE_FEATURES
+            | VIRTIO_MMIO_QUEUE_NUM_MAX
+            | VIRTIO_MMIO_INTERRUPT_STATUS
+            | VIRTIO_MMIO_SHM_LEN_LOW
+            | VIRTIO_MMIO_SHM_LEN_HIGH
+            | VIRTIO_MMIO_SHM_BASE_LOW
+            | VIRTIO_MMIO_SHM_BASE_HIGH
+            | VIRTIO_MMIO_CONFIG_GENERATION
+    )
+}
+
+/// Returns the names of the flags set in the device status register `status`.
+fn driver_status_flags(status: u8) -> Vec<&'static str> {
+    DRIVER_STATUS_FLAGS
+        .iter()
+        .filter(|(bit, _)| u32::from(status) & bit != 0)
+        .map(|(_, name)| *name)
+        .collect()
+}
+
+/// Returns the names of the flags newly set and newly cleared when the device status register
+/// goes from `old` to `new`.
+fn driver_status_changes(old: u8, new: u8) -> (Vec<&'static str>, Vec<&'static str>) {
+    (
+        driver_status_flags(new & !old),
+        driver_status_flags(old & !new),
+    )
+}
+
+/// Callback run with the queue events when a `VirtioMmioDevice` is activated.
+pub type ActivateCallback = Box<dyn FnOnce(&[Event]) + Send>;
+
+#[derive(Default)]
+struct AccessCounters {
+    control_reads: AtomicU64,
+    control_writes: AtomicU64,
+    config_reads: AtomicU64,
+    config_writes: AtomicU64,
+}
 
 /// Implements the
 /// [MMIO](http://docs.oasis-open.org/virtio/virtio/v1.0/cs04/virtio-v1.0-cs04.html#x1-1090002)
@@ -52,17 +220,94 @@ pub struct VirtioMmioDevice {
 
     interrupt: Option<Interrupt>,
     interrupt_evt: Option<IrqEdgeEvent>,
+    // Per-queue interrupts assigned with `assign_queue_irqs`, if any.
+    queue_irq_evts: Option<Vec<IrqEdgeEvent>>,
     async_intr_status: bool,
     queues: Vec<QueueConfig>,
     queue_evts: Vec<Event>,
+    // Notifications forwarded to each of `queue_evts` by the transport.
+    notify_counts: Vec<AtomicU64>,
+    // Data from the last notification of each queue written with VIRTIO_F_NOTIFICATION_DATA.
+    notify_data: Vec<Option<u32>>,
     mem: GuestMemory,
     device_feature_select: u32,
     driver_feature_select: u32,
+    // Features offered by the transport on top of the device's, and those of them the driver
+    // acknowledged.
+    transport_features: u64,
+    acked_transport_features: u64,
     queue_select: u16,
     driver_status: u8,
     mmio_base: u64,
     irq_num: u32,
-    config_generation: u32,
+    // Atomic since reads, which only borrow the transport, pick up config changes signaled by the
+    // device.
+    config_generation: AtomicU32,
+    // Config changes signaled by the device through `interrupt` already reflected in
+    // `config_generation`.
+    config_changes_seen: AtomicU64,
+    // Where queue notifications are written besides QueueNotify, if set with `set_notify_layout`.
+    notify_layout: Option<NotifyLayout>,
+    // Config captured when the driver last read `config_generation`, served to config reads until
+    // the generation changes.
+    config_snapshot: Mutex<Option<ConfigSnapshot>>,
+    version: u8,
+    vendor_id: u32,
+    // Reported in place of the device's own type, if set.
+    device_type_override: Option<u32>,
+    // Set by the host through `mark_failed`, or on out-of-spec driver behavior in strict mode,
+    // until the driver resets the device. Atomic since reads, which only borrow the transport, can
+    // fail the device too.
+    failed: AtomicBool,
+    // Whether out-of-spec driver behavior fails the device rather than only being logged.
+    strict: bool,
+    // Resets requested by the driver writing 0 to the status register.
+    reset_count: AtomicU64,
+    // Records the rings of the queues on activation, if set.
+    memory_access_tracker: Option<MemoryAccessTracker>,
+
+    // State only present while asleep.
+    sleep_state: Option<SleepState>,
+
+    access_counters: AccessCounters,
+
+    // Run once, the first time the device is activated.
+    activate_callback: Option<ActivateCallback>,
+
+    // Set while `activate` runs, to catch it being entered again if the transport is ever shared.
+    #[cfg(debug_assertions)]
+    activating: AtomicBool,
+}
+
+struct NotifyLayout {
+    base: u64,
+    multiplier: u64,
+}
+
+impl NotifyLayout {
+    /// Returns the queue notified by a write at `offset`, if it is one of the `num_queues` queues.
+    fn queue_index(&self, offset: u64, num_queues: usize) -> Option<usize> {
+        let index = offset.checked_sub(self.base)? / self.multiplier;
+        usize::try_from(index)
+            .ok()
+            .filter(|&index| index < num_queues)
+    }
+}
+
+struct ConfigSnapshot {
+    generation: u32,
+    config: Vec<u8>,
+}
+
+enum SleepState {
+    // Asleep and device hasn't been activated yet by the guest.
+    Inactive,
+    // Asleep and device has been activated by the guest.
+    Active {
+        /// The queues returned from `VirtioDevice::virtio_sleep`.
+        /// Map is from queue index -> Queue.
+        activated_queues: BTreeMap<usize, Queue>,
+    },
 }
 
 impl VirtioMmioDevice {
@@ -72,6 +317,22 @@ impl VirtioMmioDevice {
         device: Box<dyn VirtioDevice>,
         async_intr_status: bool,
     ) -> Result<Self> {
+        Self::with_version(mem, device, async_intr_status, VIRT_VERSION)
+    }
+
+    /// Constructs a new MMIO transport that reports `version` in its version register.
+    ///
+    /// Only versions 1 (legacy) and 2 are valid.
+    pub fn with_version(
+        mem: GuestMemory,
+        device: Box<dyn VirtioDevice>,
+        async_intr_status: bool,
+        version: u8,
+    ) -> Result<Self> {
+        if !(1..=2).contains(&version) {
+            return Err(base::Error::new(libc::EINVAL));
+        }
+
         let mut queue_evts = Vec::new();
         for _ in device.queue_max_sizes() {
             queue_evts.push(Event::new()?)
@@ -82,44 +343,133 @@ impl VirtioMmioDevice {
             .map(|&s| QueueConfig::new(s, device.features()))
             .collect();
 
+        let notify_counts = queue_evts.iter().map(|_| AtomicU64::new(0)).collect();
+        let notify_data = vec![None; queue_evts.len()];
+
         Ok(VirtioMmioDevice {
             device,
             device_activated: false,
             interrupt: None,
             interrupt_evt: None,
+            queue_irq_evts: None,
             async_intr_status,
             queues,
             queue_evts,
+            notify_counts,
+            notify_data,
             mem,
             device_feature_select: 0,
             driver_feature_select: 0,
+            transport_features: 0,
+            acked_transport_features: 0,
             queue_select: 0,
             driver_status: 0,
             mmio_base: 0,
             irq_num: 0,
-            config_generation: 0,
+            config_generation: AtomicU32::new(0),
+            config_changes_seen: AtomicU64::new(0),
+            notify_layout: None,
+            config_snapshot: Mutex::new(None),
+            version,
+            vendor_id: VIRT_VENDOR,
+            device_type_override: None,
+            failed: AtomicBool::new(false),
+            strict: false,
+            reset_count: AtomicU64::new(0),
+            memory_access_tracker: None,
+            sleep_state: None,
+            access_counters: AccessCounters::default(),
+            activate_callback: None,
+            #[cfg(debug_assertions)]
+            activating: AtomicBool::new(false),
         })
     }
+
+    /// Constructs a new MMIO transport with previously configured queues and driver status, such
+    /// as when resuming a VM, so the registers read back the same as before it was suspended.
+    ///
+    /// `queues` must match the device's queues in number and maximum size, and carry the features
+    /// acked by the driver. The device itself is not activated; its own state, including acked
+    /// features, has to be restored separately. Fails with `EINVAL` if the queues don't match or
+    /// `driver_status` has DRIVER_OK set, as the device would then not be activated until some
+    /// unrelated register write.
+    pub fn from_parts(
+        mem: GuestMemory,
+        device: Box<dyn VirtioDevice>,
+        async_intr_status: bool,
+        queues: Vec<QueueConfig>,
+        driver_status: u8,
+    ) -> Result<Self> {
+        let max_sizes_match = queues
+            .iter()
+            .map(QueueConfig::max_size)
+            .eq(device.queue_max_sizes().iter().copied());
+        if !max_sizes_match || driver_status & VIRTIO_CONFIG_S_DRIVER_OK as u8 != 0 {
+            return Err(base::Error::new(libc::EINVAL));
+        }
+
+        let mut mmio_device = Self::new(mem, device, async_intr_status)?;
+        mmio_device.queues = queues;
+        mmio_device.driver_status = driver_status;
+        Ok(mmio_device)
+    }
+
     pub fn ioevents(&self) -> Vec<(&Event, u64, Datamatch)> {
         self.queue_evts
             .iter()
             .enumerate()
-            .map(|(i, event)| {
-                (
+            .map(|(i, event)| match &self.notify_layout {
+                Some(layout) => (
+                    event,
+                    self.mmio_base + layout.base + i as u64 * layout.multiplier,
+                    Datamatch::AnyLength,
+                ),
+                None => (
                     event,
                     self.mmio_base + VIRTIO_MMIO_QUEUE_NOTIFY as u64,
                     Datamatch::U32(Some(i.try_into().unwrap())),
-                )
+                ),
             })
             .collect()
     }
 
+    /// Makes the driver notify queue `i` by writing at offset `base + i * multiplier` of the
+    /// device's region, as with the notify capability of virtio-pci, rather than by writing the
+    /// queue index to the QueueNotify register at 0x50. Writes anywhere in
+    /// `[base, base + multiplier)` notify queue 0, and so on.
+    ///
+    /// Writes in that range are taken as notifications even if they hit a register or the config
+    /// space, so it should be placed where the driver doesn't otherwise write. The QueueNotify
+    /// register keeps working as well. Fails with `EINVAL` if `multiplier` is 0 or the range of
+    /// all queues doesn't fit in the device's region.
+    pub fn set_notify_layout(&mut self, base: u64, multiplier: u64) -> Result<()> {
+        let end = (self.queue_evts.len() as u64)
+            .checked_mul(multiplier)
+            .and_then(|len| len.checked_add(base));
+        let fits = matches!(end, Some(end) if end <= VIRTIO_MMIO_REGION_SZ);
+        if multiplier == 0 || !fits {
+            return Err(base::Error::new(libc::EINVAL));
+        }
+        self.notify_layout = Some(NotifyLayout { base, multiplier });
+        Ok(())
+    }
+
     fn is_driver_ready(&self) -> bool {
         let ready_bits = (VIRTIO_CONFIG_S_ACKNOWLEDGE
             | VIRTIO_CONFIG_S_DRIVER
             | VIRTIO_CONFIG_S_DRIVER_OK
             | VIRTIO_CONFIG_S_FEATURES_OK) as u8;
-        self.driver_status == ready_bits && self.driver_status & VIRTIO_CONFIG_S_FAILED as u8 == 0
+        self.driver_status == ready_bits
+            && self.driver_status & VIRTIO_CONFIG_S_FAILED as u8 == 0
+            && !self.is_failed()
+    }
+
+    /// Determines if the driver acked VIRTIO_F_NOTIFICATION_DATA, which changes the format of
+    /// queue notifications.
+    fn notification_data_negotiated(&self) -> bool {
+        self.queues
+            .first()
+            .is_some_and(|q| q.acked_features() & (1 << VIRTIO_F_NOTIFICATION_DATA) != 0)
     }
 
     /// Determines if the driver has requested the device reset itself
@@ -127,12 +477,92 @@ impl VirtioMmioDevice {
         self.driver_status == DEVICE_RESET as u8
     }
 
+    /// Checks that the rings of every ready queue meet the alignment required by the virtio spec,
+    /// logging the first one that doesn't.
+    fn are_queues_aligned(&self) -> bool {
+        for (index, queue) in self.queues.iter().enumerate().filter(|(_, q)| q.ready()) {
+            let rings = [
+                ("descriptor table", queue.desc_table(), 16),
+                ("available ring", queue.avail_ring(), 2),
+                ("used ring", queue.used_ring(), 4),
+            ];
+            for (name, addr, align) in rings {
+                if addr.offset() % align != 0 {
+                    warn!(
+                        "{}: queue {} {} at {} is not {}-byte aligned",
+                        self.debug_label(),
+                        index,
+                        name,
+                        addr,
+                        align
+                    );
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
     fn device_type(&self) -> u32 {
-        self.device.device_type() as u32
+        self.device_type_override
+            .unwrap_or(self.device.device_type() as u32)
+    }
+
+    /// Sets the vendor ID reported to the driver, which is 'CRVM' by default.
+    pub fn with_vendor_id(mut self, vendor_id: u32) -> Self {
+        self.vendor_id = vendor_id;
+        self
+    }
+
+    /// Sets the device ID reported to the driver instead of the one from
+    /// `VirtioDevice::device_type`. Everything else is still handled by the wrapped device.
+    pub fn with_device_type_override(mut self, ty: u32) -> Self {
+        self.device_type_override = Some(ty);
+        self
+    }
+
+    /// Offers `features` to the driver along with the device's own features, e.g. ring features
+    /// that the queues support whatever the device. Those the driver acknowledges are passed to
+    /// the device with `VirtioDevice::set_transport_features` on activation.
+    pub fn with_transport_features(mut self, features: u64) -> Self {
+        self.transport_features = features;
+        for queue in self.queues.iter_mut() {
+            queue.add_features(features);
+        }
+        self
+    }
+
+    /// Records the guest memory regions the device may access in `tracker`, see `dirty_regions`.
+    pub fn with_memory_access_tracker(mut self, tracker: MemoryAccessTracker) -> Self {
+        self.memory_access_tracker = Some(tracker);
+        self
     }
 
     /// Activates the underlying `VirtioDevice`. `assign_irq` has to be called first.
     fn activate(&mut self) -> anyhow::Result<()> {
+        #[cfg(debug_assertions)]
+        {
+            let reentered = self.activating.swap(true, Ordering::SeqCst);
+            assert!(
+                !reentered,
+                "{}: activate entered while already activating",
+                self.debug_label()
+            );
+        }
+        let result = self.activate_device();
+        #[cfg(debug_assertions)]
+        self.activating.store(false, Ordering::SeqCst);
+        result
+    }
+
+    fn activate_device(&mut self) -> anyhow::Result<()> {
+        if self.device_activated {
+            return Ok(());
+        }
+
+        let _trace = cros_tracing::trace_event!(VirtioMmio, "activate", self.device.device_type());
+        let start = std::time::Instant::now();
+
         let interrupt_evt = if let Some(ref evt) = self.interrupt_evt {
             evt.try_clone()
                 .with_context(|| format!("{} failed to clone interrupt_evt", self.debug_label()))?
@@ -141,8 +571,23 @@ impl VirtioMmioDevice {
         };
 
         let mem = self.mem.clone();
-        let interrupt = Interrupt::new_mmio(interrupt_evt, self.async_intr_status);
-        self.interrupt = Some(interrupt.clone());
+        let interrupt = match &self.queue_irq_evts {
+            Some(queue_irq_evts) => {
+                let queue_irq_evts = queue_irq_evts
+                    .iter()
+                    .map(IrqEdgeEvent::try_clone)
+                    .collect::<Result<Vec<_>>>()
+                    .with_context(|| {
+                        format!("{} failed to clone queue_irq_evts", self.debug_label())
+                    })?;
+                Interrupt::new_mmio_with_queue_irqs(
+                    interrupt_evt,
+                    queue_irq_evts,
+                    self.async_intr_status,
+                )
+            }
+            None => Interrupt::new_mmio(interrupt_evt, self.async_intr_status),
+        };
 
         // Use ready queues and their events.
         let queues = self
@@ -160,51 +605,311 @@ impl VirtioMmioDevice {
                         .context("failed to activate queue")?,
                 ))
             })
-            .collect::<anyhow::Result<BTreeMap<usize, Queue>>>()?;
+            .collect::<anyhow::Result<BTreeMap<usize, Queue>>>();
+        let queues = match queues {
+            Ok(queues) => queues,
+            Err(e) => {
+                // Nothing has used the queues yet, so they can be activated again on a retry.
+                self.queues.iter_mut().for_each(QueueConfig::deactivate);
+                return Err(e);
+            }
+        };
 
+        self.interrupt = Some(interrupt.clone());
+        *self.config_changes_seen.get_mut() = 0;
+        self.device
+            .set_transport_features(self.acked_transport_features);
         if let Err(e) = self.device.activate(mem, interrupt, queues) {
             error!("{} activate failed: {:#}", self.debug_label(), e);
             self.driver_status |= VIRTIO_CONFIG_S_NEEDS_RESET as u8;
+            // Leave the transport as it was before so that activation can be retried.
+            self.interrupt = None;
+            self.queues.iter_mut().for_each(QueueConfig::deactivate);
         } else {
             self.device_activated = true;
+            debug!("{}: activated in {:?}", self.debug_label(), start.elapsed());
+            if let Some(tracker) = &mut self.memory_access_tracker {
+                for queue in self.queues.iter().filter(|q| q.ready()) {
+                    for (addr, len) in queue.ring_regions() {
+                        tracker.record(addr, len);
+                    }
+                }
+            }
+            if let Some(callback) = self.activate_callback.take() {
+                callback(&self.queue_evts);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Registers `callback` to be run with the queue events once the device has been successfully
+    /// activated by the driver. The callback only runs for the first activation.
+    pub fn on_activate(&mut self, callback: ActivateCallback) {
+        self.activate_callback = Some(callback);
+    }
+
+    /// Returns the values of the identification registers.
+    pub fn identity(&self) -> VirtioMmioIdentity {
+        VirtioMmioIdentity {
+            magic: VIRT_MAGIC,
+            version: self.version.into(),
+            device_id: self.device_type(),
+            vendor_id: self.vendor_id,
+        }
+    }
+
+    /// Returns the configuration the driver has programmed for queue `index`, or `None` if there is
+    /// no such queue.
+    pub fn queue_config(&self, index: usize) -> Option<VirtioMmioQueueInfo> {
+        self.queues.get(index).map(|q| VirtioMmioQueueInfo {
+            desc_table: q.desc_table(),
+            avail_ring: q.avail_ring(),
+            used_ring: q.used_ring(),
+            size: q.size(),
+            ready: q.ready(),
+        })
+    }
+
+    /// Returns the inner virtio device if it is a `T`.
+    pub fn device_ref<T: VirtioDevice + 'static>(&self) -> Option<&T> {
+        self.device.as_ref().as_any().downcast_ref()
+    }
+
+    /// Returns the inner virtio device mutably if it is a `T`.
+    pub fn device_mut<T: VirtioDevice + 'static>(&mut self) -> Option<&mut T> {
+        self.device.as_mut().as_any_mut().downcast_mut()
+    }
+
+    /// Returns the number of queues the device has.
+    pub fn num_queues(&self) -> usize {
+        self.queues.len()
+    }
+
+    /// Returns the maximum size of queue `index`, or `None` if there is no such queue.
+    pub fn queue_max_size(&self, index: usize) -> Option<u16> {
+        self.queues.get(index).map(QueueConfig::max_size)
+    }
+
+    /// Raises a used ring interrupt as if the device had placed buffers in a used ring.
+    ///
+    /// Fails if the device has not been activated by the driver yet.
+    pub fn signal_used_queue(&self) -> anyhow::Result<()> {
+        match &self.interrupt {
+            Some(interrupt) if self.device_activated => {
+                interrupt.signal_used_queue(VIRTIO_MSI_NO_VECTOR);
+                Ok(())
+            }
+            _ => Err(anyhow!("{} is not activated", self.debug_label())),
         }
+    }
 
+    /// Signals the event of every ready queue once, as if the driver had notified each of them,
+    /// so that the device processes buffers made available before it last stopped looking, e.g.
+    /// after a restore. These kicks are not counted by `notify_count`.
+    ///
+    /// Fails if the device has not been activated by the driver yet.
+    pub fn kick_all_queues(&self) -> anyhow::Result<()> {
+        if !self.device_activated {
+            return Err(anyhow!("{} is not activated", self.debug_label()));
+        }
+        for (index, (queue, evt)) in self.queues.iter().zip(&self.queue_evts).enumerate() {
+            if queue.ready() {
+                evt.signal()
+                    .with_context(|| format!("failed to kick queue {}", index))?;
+            }
+        }
         Ok(())
     }
 
+    /// Returns how many notifications for queue `index` the transport has forwarded to the device,
+    /// or `None` if there is no such queue.
+    ///
+    /// Notifications delivered through `ioevents` go straight to the device and are not counted.
+    pub fn notify_count(&self, index: usize) -> Option<u64> {
+        self.notify_counts
+            .get(index)
+            .map(|count| count.load(Ordering::Relaxed))
+    }
+
+    /// Returns how many times the driver has reset the device by writing 0 to the status register
+    /// while it was activated, e.g. because the driver was reloaded or the guest kexec'd.
+    pub fn reset_count(&self) -> u64 {
+        self.reset_count.load(Ordering::Relaxed)
+    }
+
+    /// Returns the data the driver passed with the last notification of queue `index`, or `None`
+    /// if there is no such queue or it has not been notified with data.
+    ///
+    /// Notification data is only sent when VIRTIO_F_NOTIFICATION_DATA was negotiated. It holds the
+    /// upper 16 bits of the value written to the notify register: the offset of the next available
+    /// ring entry, plus the wrap counter for packed queues.
+    pub fn notify_data(&self, index: usize) -> Option<u32> {
+        self.notify_data.get(index).copied().flatten()
+    }
+
+    /// Writes each `(offset, value)` pair to the registers in order, as a sequence of 4-byte
+    /// driver writes would, e.g. to replay a recorded access log.
+    pub fn apply_writes(&mut self, ops: &[(u64, u32)]) {
+        for &(offset, val) in ops {
+            let info = self.register_access(offset);
+            self.write(info, &val.to_le_bytes());
+        }
+    }
+
+    /// Reads the register at each of `offsets` in order, as a sequence of 4-byte driver reads
+    /// would.
+    pub fn read_batch(&mut self, offsets: &[u64]) -> Vec<u32> {
+        offsets
+            .iter()
+            .map(|&offset| {
+                let info = self.register_access(offset);
+                let mut data = [0u8; 4];
+                self.read(info, &mut data);
+                u32::from_le_bytes(data)
+            })
+            .collect()
+    }
+
+    fn register_access(&self, offset: u64) -> BusAccessInfo {
+        BusAccessInfo {
+            offset,
+            address: self.mmio_base + offset,
+            id: 0,
+        }
+    }
+
+    /// Marks the device as failed, e.g. after its worker stopped unexpectedly. The driver sees
+    /// the FAILED bit set in the status register until it resets the device, and the device is not
+    /// activated until then. A device that is already active keeps running.
+    pub fn mark_failed(&mut self) {
+        self.failed.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns true if the device was marked as failed, by `mark_failed` or in strict mode, since
+    /// the driver last reset it.
+    pub fn is_failed(&self) -> bool {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    /// Makes out-of-spec driver behavior fail the device as with `mark_failed`, rather than only
+    /// being logged and ignored, e.g. to catch buggy drivers in tests. This covers control register
+    /// accesses that are not 4 bytes wide, writes to read-only registers, feature acknowledgements
+    /// after FEATURES_OK and queue register writes after activation. Off by default.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Reports out-of-spec driver behavior described by `args`, failing the device in strict mode.
+    fn driver_error(&self, args: std::fmt::Arguments) {
+        if self.strict {
+            error!("{}: {}, failing the device", self.debug_label(), args);
+            self.failed.store(true, Ordering::Relaxed);
+        } else {
+            warn!("{}: {}", self.debug_label(), args);
+        }
+    }
+
+    /// Increments the configuration generation read by the driver at offset 0xfc, as if the
+    /// device configuration had changed. Drivers read it before and after reading the config
+    /// space and retry if it changed, so this can be used to exercise that retry.
+    pub fn bump_config_generation(&mut self) {
+        self.config_generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Moves to a new configuration generation if the device signaled a config change since the
+    /// last call, so that the driver doesn't keep reading a stale snapshot of the config.
+    fn sync_config_generation(&self) {
+        let Some(interrupt) = &self.interrupt else {
+            return;
+        };
+        let changes = interrupt.config_change_count();
+        if self.config_changes_seen.swap(changes, Ordering::SeqCst) != changes {
+            self.config_generation.fetch_add(1, Ordering::SeqCst);
+            *self.config_snapshot.lock() = None;
+        }
+    }
+
+    /// Returns the guest memory regions the device may have accessed, as (address, length) pairs.
+    /// Always empty unless a tracker was set with `with_memory_access_tracker`.
+    pub fn dirty_regions(&self) -> Vec<(GuestAddress, usize)> {
+        self.memory_access_tracker
+            .as_ref()
+            .map(|tracker| tracker.regions().to_vec())
+            .unwrap_or_default()
+    }
+
+    /// Returns the number of guest accesses to this device so far.
+    pub fn stats(&self) -> VirtioMmioStats {
+        let counters = &self.access_counters;
+        VirtioMmioStats {
+            control_reads: counters.control_reads.load(Ordering::Relaxed),
+            control_writes: counters.control_writes.load(Ordering::Relaxed),
+            config_reads: counters.config_reads.load(Ordering::Relaxed),
+            config_writes: counters.config_writes.load(Ordering::Relaxed),
+        }
+    }
+
     fn read_mmio(&self, info: BusAccessInfo, data: &mut [u8]) {
-        if data.len() != std::mem::size_of::<u32>() {
-            warn!(
-                "{}: unsupported read length {}, only support 4 bytes read",
-                self.debug_label(),
-                data.len()
-            );
+        // Zero-length accesses are probes from the bus plumbing rather than guest accesses.
+        if data.is_empty() {
             return;
         }
 
+        let counter = if info.offset >= VIRTIO_MMIO_CONFIG as u64 {
+            &self.access_counters.config_reads
+        } else {
+            &self.access_counters.control_reads
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        // The config space is read with the width of the field, unlike the control registers.
         if info.offset >= VIRTIO_MMIO_CONFIG as u64 {
-            self.device
-                .read_config(info.offset - VIRTIO_MMIO_CONFIG as u64, data);
+            self.read_config(info.offset - VIRTIO_MMIO_CONFIG as u64, data);
+            return;
+        }
+
+        if data.len() != std::mem::size_of::<u32>() {
+            self.driver_error(format_args!(
+                "unsupported {}-byte read at offset {:#x}, only 4-byte reads are supported",
+                data.len(),
+                info.offset
+            ));
+            // Give the guest a defined value rather than whatever was in its buffer.
+            data.fill(0);
             return;
         }
 
         let val = match info.offset as u32 {
-            VIRTIO_MMIO_MAGIC_VALUE => VIRT_MAGIC,
-            VIRTIO_MMIO_VERSION => VIRT_VERSION.into(), // legacy is not supported
-            VIRTIO_MMIO_DEVICE_ID => self.device_type(),
-            VIRTIO_MMIO_VENDOR_ID => VIRT_VENDOR,
+            VIRTIO_MMIO_MAGIC_VALUE => self.identity().magic,
+            VIRTIO_MMIO_VERSION => self.identity().version,
+            VIRTIO_MMIO_DEVICE_ID => self.identity().device_id,
+            VIRTIO_MMIO_VENDOR_ID => self.identity().vendor_id,
             VIRTIO_MMIO_DEVICE_FEATURES => {
-                if self.device_feature_select < 2 {
-                    (self.device.features() >> (self.device_feature_select * 32)) as u32
+                if self.device_feature_select < NUM_FEATURE_WORDS {
+                    let features = self.device.features() | self.transport_features;
+                    (features >> (self.device_feature_select * 32)) as u32
                 } else {
                     0
                 }
             }
-            VIRTIO_MMIO_QUEUE_NUM_MAX => self.with_queue(|q| q.max_size()).unwrap_or(0).into(),
+            VIRTIO_MMIO_QUEUE_NUM_MAX => match self.with_queue(|q| q.max_size()) {
+                Some(max_size) => max_size.into(),
+                None => {
+                    warn!(
+                        "{}: read of {} with out of range queue {} selected",
+                        self.debug_label(),
+                        reg_name(info.offset),
+                        self.queue_select,
+                    );
+                    0
+                }
+            },
             VIRTIO_MMIO_QUEUE_PFN => {
                 warn!(
-                    "{}: read from legacy register {}, in non-legacy mode",
+                    "{}: read from legacy register {} ({:#x}), in non-legacy mode",
                     self.debug_label(),
+                    reg_name(info.offset),
                     info.offset,
                 );
                 0
@@ -217,10 +922,26 @@ impl VirtioMmioDevice {
                     0
                 }
             }
-            VIRTIO_MMIO_STATUS => self.driver_status.into(),
-            VIRTIO_MMIO_CONFIG_GENERATION => self.config_generation,
+            VIRTIO_MMIO_STATUS => {
+                let failed = if self.is_failed() {
+                    VIRTIO_CONFIG_S_FAILED
+                } else {
+                    0
+                };
+                u32::from(self.driver_status) | failed
+            }
+            VIRTIO_MMIO_CONFIG_GENERATION => {
+                self.sync_config_generation();
+                self.snapshot_config();
+                self.config_generation.load(Ordering::SeqCst)
+            }
             _ => {
-                warn!("{}: unsupported read address {}", self.debug_label(), info);
+                warn!(
+                    "{}: unsupported read address {} ({})",
+                    self.debug_label(),
+                    info,
+                    reg_name(info.offset)
+                );
                 return;
             }
         };
@@ -230,23 +951,51 @@ impl VirtioMmioDevice {
     }
 
     fn write_mmio(&mut self, info: BusAccessInfo, data: &[u8]) {
-        if data.len() != std::mem::size_of::<u32>() {
-            warn!(
-                "{}: unsupported write length {}, only support 4 bytes write",
-                self.debug_label(),
-                data.len()
-            );
+        // Zero-length accesses are probes from the bus plumbing rather than guest accesses.
+        if data.is_empty() {
             return;
         }
 
-        if info.offset >= VIRTIO_MMIO_CONFIG as u64 {
-            self.device
-                .write_config(info.offset - VIRTIO_MMIO_CONFIG as u64, data);
+        let counter = if info.offset >= VIRTIO_MMIO_CONFIG as u64 {
+            &self.access_counters.config_writes
+        } else {
+            &self.access_counters.control_writes
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        // The config space is written with the width of the field, unlike the control registers.
+        let is_config = info.offset >= VIRTIO_MMIO_CONFIG as u64;
+        let val = match <[u8; 4]>::try_from(data) {
+            Ok(bytes) => u32::from_le_bytes(bytes),
+            Err(_) if is_config => 0,
+            Err(_) => {
+                self.driver_error(format_args!(
+                    "ignoring unsupported {}-byte write at offset {:#x}, only 4-byte writes are \
+                     supported",
+                    data.len(),
+                    info.offset
+                ));
+                return;
+            }
+        };
+
+        if let Some(index) = self
+            .notify_layout
+            .as_ref()
+            .filter(|_| data.len() == std::mem::size_of::<u32>())
+            .and_then(|layout| layout.queue_index(info.offset, self.queue_evts.len()))
+        {
+            // The queue is given by the offset, so only notification data is taken from the
+            // value.
+            let notify_data = self.notification_data_negotiated().then_some(val >> 16);
+            self.notify_queue(index, notify_data);
             return;
         }
 
-        // This unwrap cannot fail since data.len() is checked.
-        let val = u32::from_le_bytes(data.try_into().unwrap());
+        if is_config {
+            self.write_config(info.offset - VIRTIO_MMIO_CONFIG as u64, data);
+            return;
+        }
 
         macro_rules! hi {
             ($q:expr, $get:ident, $set:ident, $x:expr) => {
@@ -259,13 +1008,77 @@ impl VirtioMmioDevice {
             };
         }
 
+        match info.offset as u32 {
+            // The activated device owns its queues until the next reset, so the queue
+            // configuration must not change underneath it. Clearing QueueReady is let through to
+            // take a single queue back from the device.
+            VIRTIO_MMIO_QUEUE_READY if self.device_activated && val != 0 => {
+                self.driver_error(format_args!(
+                    "ignoring write to queue register {} after device was activated",
+                    reg_name(info.offset),
+                ));
+                return;
+            }
+            VIRTIO_MMIO_QUEUE_NUM
+            | VIRTIO_MMIO_QUEUE_DESC_LOW
+            | VIRTIO_MMIO_QUEUE_DESC_HIGH
+            | VIRTIO_MMIO_QUEUE_AVAIL_LOW
+            | VIRTIO_MMIO_QUEUE_AVAIL_HIGH
+            | VIRTIO_MMIO_QUEUE_USED_LOW
+            | VIRTIO_MMIO_QUEUE_USED_HIGH
+                if self.device_activated =>
+            {
+                self.driver_error(format_args!(
+                    "ignoring write to queue register {} after device was activated",
+                    reg_name(info.offset),
+                ));
+                return;
+            }
+            // Features are final once the driver has set FEATURES_OK.
+            VIRTIO_MMIO_DRIVER_FEATURES
+                if self.driver_status & VIRTIO_CONFIG_S_FEATURES_OK as u8 != 0 =>
+            {
+                self.driver_error(format_args!(
+                    "ignoring feature acknowledgement 0x{:x} after FEATURES_OK",
+                    val,
+                ));
+                return;
+            }
+            VIRTIO_MMIO_DEVICE_FEATURES_SEL | VIRTIO_MMIO_DRIVER_FEATURES_SEL
+                if val >= NUM_FEATURE_WORDS =>
+            {
+                // The selection is still stored below, which makes the feature registers read as
+                // zero and ignore writes until a valid word is selected.
+                warn!(
+                    "{}: out of range feature word {} selected in {}",
+                    self.debug_label(),
+                    val,
+                    reg_name(info.offset),
+                );
+            }
+            VIRTIO_MMIO_QUEUE_SEL if val as usize >= self.queues.len() => {
+                // As with the feature words, the selection is still stored below, so the queue
+                // registers read as zero and ignore writes until a valid queue is selected.
+                warn!(
+                    "{}: out of range queue {} selected, the device has {} queues",
+                    self.debug_label(),
+                    val,
+                    self.queues.len(),
+                );
+            }
+            _ => {}
+        }
+
         match info.offset as u32 {
             VIRTIO_MMIO_DEVICE_FEATURES_SEL => self.device_feature_select = val,
             VIRTIO_MMIO_DRIVER_FEATURES_SEL => self.driver_feature_select = val,
             VIRTIO_MMIO_DRIVER_FEATURES => {
-                if self.driver_feature_select < 2 {
+                if self.driver_feature_select < NUM_FEATURE_WORDS {
                     let features: u64 = (val as u64) << (self.driver_feature_select * 32);
-                    self.device.ack_features(features);
+                    // Features only the transport offers are not the device's to acknowledge.
+                    let transport_only = self.transport_features & !self.device.features();
+                    self.device.ack_features(features & !transport_only);
+                    self.acked_transport_features |= features & self.transport_features;
                     for queue in self.queues.iter_mut() {
                         queue.ack_features(features);
                     }
@@ -277,30 +1090,75 @@ impl VirtioMmioDevice {
                 }
             }
             VIRTIO_MMIO_GUEST_PAGE_SIZE => warn!(
-                "{}: write to legacy register {}, in non-legacy mode",
+                "{}: write to legacy register {} ({:#x}), in non-legacy mode",
                 self.debug_label(),
+                reg_name(info.offset),
                 info.offset,
             ),
-            VIRTIO_MMIO_QUEUE_SEL => self.queue_select = val as u16,
+            // Saturate rather than truncate, so an out of range selection can't alias a valid one.
+            VIRTIO_MMIO_QUEUE_SEL => self.queue_select = val.try_into().unwrap_or(u16::MAX),
             VIRTIO_MMIO_QUEUE_NUM => self.with_queue_mut(|q| q.set_size(val as u16)),
             VIRTIO_MMIO_QUEUE_ALIGN => warn!(
-                "{}: write to legacy register {}, in non-legacy mode",
+                "{}: write to legacy register {} ({:#x}), in non-legacy mode",
                 self.debug_label(),
+                reg_name(info.offset),
                 info.offset,
             ),
             VIRTIO_MMIO_QUEUE_PFN => warn!(
-                "{}: write to legacy register {}, in non-legacy mode",
+                "{}: write to legacy register {} ({:#x}), in non-legacy mode",
                 self.debug_label(),
+                reg_name(info.offset),
                 info.offset,
             ),
-            VIRTIO_MMIO_QUEUE_READY => self.with_queue_mut(|q| q.set_ready(val == 1)),
-            VIRTIO_MMIO_QUEUE_NOTIFY => {} // Handled with ioevents.
+            VIRTIO_MMIO_QUEUE_READY => {
+                if val == 0 && self.device_activated && !self.take_back_queue() {
+                    return;
+                }
+                // With per-queue interrupts, a queue's vector selects its interrupt.
+                let per_queue_vector = self.queue_irq_evts.is_some().then_some(self.queue_select);
+                self.with_queue_mut(|q| {
+                    if val == 0 {
+                        // Clearing ready resets just this queue. The negotiated features still
+                        // apply once the driver sets it up again.
+                        let acked_features = q.acked_features();
+                        q.reset();
+                        q.ack_features(acked_features);
+                    } else {
+                        if let Some(vector) = per_queue_vector {
+                            q.set_vector(vector);
+                        }
+                        q.set_ready(val == 1);
+                    }
+                })
+            }
+            // Normally handled with ioevents, but forward notifications that reach the bus.
+            VIRTIO_MMIO_QUEUE_NOTIFY => {
+                // With notification data, the queue index is only the low 16 bits.
+                let (index, notify_data) = if self.notification_data_negotiated() {
+                    ((val & 0xffff) as usize, Some(val >> 16))
+                } else {
+                    (val as usize, None)
+                };
+                self.notify_queue(index, notify_data);
+            }
             VIRTIO_MMIO_INTERRUPT_ACK => {
                 if let Some(interrupt) = &self.interrupt {
                     interrupt.clear_interrupt_status_bits(val as u8)
                 }
             }
-            VIRTIO_MMIO_STATUS => self.driver_status = val as u8,
+            VIRTIO_MMIO_STATUS => {
+                let (set, cleared) = driver_status_changes(self.driver_status, val as u8);
+                for flag in set {
+                    debug!("{}: driver status {} set", self.debug_label(), flag);
+                }
+                for flag in cleared {
+                    debug!("{}: driver status {} cleared", self.debug_label(), flag);
+                }
+                self.driver_status = val as u8;
+                if self.is_reset_requested() {
+                    *self.failed.get_mut() = false;
+                }
+            }
             VIRTIO_MMIO_QUEUE_DESC_LOW => {
                 self.with_queue_mut(|q| lo!(q, desc_table, set_desc_table, val))
             }
@@ -319,51 +1177,211 @@ impl VirtioMmioDevice {
             VIRTIO_MMIO_QUEUE_USED_HIGH => {
                 self.with_queue_mut(|q| hi!(q, used_ring, set_used_ring, val))
             }
+            _ if is_read_only_reg(info.offset) => {
+                self.driver_error(format_args!(
+                    "ignoring write of 0x{:x} to read-only register {} ({:#x})",
+                    val,
+                    reg_name(info.offset),
+                    info.offset,
+                ));
+                return;
+            }
             _ => {
-                warn!("{}: unsupported write address {}", self.debug_label(), info);
+                warn!(
+                    "{}: unsupported write address {} ({})",
+                    self.debug_label(),
+                    info,
+                    reg_name(info.offset)
+                );
                 return;
             }
         };
 
-        if !self.device_activated && self.is_driver_ready() {
+        if !self.device_activated && self.is_driver_ready() && self.are_queues_aligned() {
             if let Err(e) = self.activate() {
                 error!("failed to activate device: {:#}", e);
             }
         }
 
-        // Device has been reset by the driver
-        if self.device_activated && self.is_reset_requested() {
-            if let Err(e) = self.device.reset() {
-                error!("failed to reset {} device: {:#}", self.debug_label(), e);
-            } else {
-                self.device_activated = false;
-                // reset queues
-                self.queues.iter_mut().for_each(QueueConfig::reset);
-                // select queue 0 by default
-                self.queue_select = 0;
-                // reset interrupt
-                self.interrupt = None;
-            }
+        // Device has been reset by the driver. Only count resets that succeeded.
+        if self.device_activated && self.is_reset_requested() && self.reset_device() {
+            self.reset_count.fetch_add(1, Ordering::Relaxed);
         }
-    }
-
-    fn with_queue<U, F>(&self, f: F) -> Option<U>
-    where
-        F: FnOnce(&QueueConfig) -> U,
-    {
-        self.queues.get(self.queue_select as usize).map(f)
-    }
 
-    fn with_queue_mut<F>(&mut self, f: F)
-    where
-        F: FnOnce(&mut QueueConfig),
-    {
-        if let Some(queue) = self.queues.get_mut(self.queue_select as usize) {
-            f(queue);
+        // The driver has given up on the device, so stop it until the driver resets it.
+        if self.device_activated && self.driver_status & VIRTIO_CONFIG_S_FAILED as u8 != 0 {
+            warn!("{}: driver marked the device as failed", self.debug_label());
+            self.reset_device();
         }
     }
 
-    pub fn allocate_regions(
+    /// Forwards a driver notification of queue `index` to the device, if it has been activated.
+    fn notify_queue(&mut self, index: usize, notify_data: Option<u32>) {
+        if !self.device_activated {
+            return;
+        }
+        cros_tracing::trace_simple_print!(
+            VirtioMmio,
+            "{}: queue {} notified (data {:?})",
+            self.debug_label(),
+            index,
+            notify_data
+        );
+        if let Some(evt) = self.queue_evts.get(index) {
+            self.notify_counts[index].fetch_add(1, Ordering::Relaxed);
+            if let Some(data) = notify_data {
+                self.notify_data[index] = notify_data;
+                self.device.queue_notified(index, data);
+            }
+            if let Err(e) = evt.signal() {
+                error!(
+                    "{}: failed to notify queue {}: {}",
+                    self.debug_label(),
+                    index,
+                    e
+                );
+            }
+        }
+    }
+
+    /// Takes the selected queue back from the activated device so that the driver can reset it.
+    /// Returns `false` if the device still uses the queue.
+    fn take_back_queue(&mut self) -> bool {
+        let index = self.queue_select as usize;
+        if !self.with_queue(|q| q.ready()).unwrap_or(false) {
+            // The device was never given this queue.
+            return true;
+        }
+        match self.device.reset_queue(index) {
+            Ok(_) => {
+                self.notify_data[index] = None;
+                true
+            }
+            Err(e) => {
+                error!(
+                    "{}: failed to reset queue {}: {:#}",
+                    self.debug_label(),
+                    index,
+                    e
+                );
+                false
+            }
+        }
+    }
+
+    /// Resets the inner device, taking back its queues. Returns `false` if the device failed to
+    /// reset and is still activated.
+    fn reset_device(&mut self) -> bool {
+        if let Err(e) = self.device.reset() {
+            error!("failed to reset {} device: {:#}", self.debug_label(), e);
+            false
+        } else {
+            self.device_activated = false;
+            // reset queues
+            self.queues.iter_mut().for_each(QueueConfig::reset);
+            self.notify_data.fill(None);
+            self.acked_transport_features = 0;
+            // select queue 0 by default
+            self.queue_select = 0;
+            // reset interrupt
+            self.interrupt = None;
+            *self.config_snapshot.get_mut() = None;
+            true
+        }
+    }
+
+    /// Captures the device's config for the current generation, so that the driver's reads
+    /// between two reads of the same generation see consistent values even if the device changes
+    /// its config meanwhile. Devices that don't report their config length are read directly.
+    fn snapshot_config(&self) {
+        let Some(config_len) = self.device.config_len() else {
+            return;
+        };
+        let mut config = vec![0; config_len];
+        self.device.read_config(0, &mut config);
+        *self.config_snapshot.lock() = Some(ConfigSnapshot {
+            generation: self.config_generation.load(Ordering::SeqCst),
+            config,
+        });
+    }
+
+    /// Reads from the device's configuration space, reading bytes beyond the length reported by
+    /// the device as zero. Served from the snapshot taken by `snapshot_config` while it is for
+    /// the current generation.
+    fn read_config(&self, offset: u64, data: &mut [u8]) {
+        self.sync_config_generation();
+        let generation = self.config_generation.load(Ordering::SeqCst);
+        if let Some(snapshot) = self
+            .config_snapshot
+            .lock()
+            .as_ref()
+            .filter(|snapshot| snapshot.generation == generation)
+        {
+            let start = usize::try_from(offset)
+                .unwrap_or(usize::MAX)
+                .min(snapshot.config.len());
+            let in_range = std::cmp::min(data.len(), snapshot.config.len() - start);
+            data[..in_range].copy_from_slice(&snapshot.config[start..start + in_range]);
+            data[in_range..].fill(0);
+            return;
+        }
+
+        let in_range = self.config_bytes_in_range(offset, data.len());
+        if in_range < data.len() {
+            data.fill(0);
+        }
+        if in_range > 0 {
+            self.device.read_config(offset, &mut data[..in_range]);
+        }
+    }
+
+    /// Writes to the device's configuration space, dropping bytes beyond the length reported by
+    /// the device.
+    fn write_config(&mut self, offset: u64, data: &[u8]) {
+        let in_range = self.config_bytes_in_range(offset, data.len());
+        if in_range < data.len() {
+            warn!(
+                "{}: dropping {} bytes of config write at offset {} past the end of the config",
+                self.debug_label(),
+                data.len() - in_range,
+                offset,
+            );
+        }
+        if in_range > 0 {
+            self.device.write_config(offset, &data[..in_range]);
+            // Later reads must see what the driver wrote.
+            *self.config_snapshot.get_mut() = None;
+        }
+    }
+
+    /// Returns how many of the `len` bytes at config space `offset` are within the device's
+    /// config.
+    fn config_bytes_in_range(&self, offset: u64, len: usize) -> usize {
+        match self.device.config_len() {
+            Some(config_len) => {
+                std::cmp::min(len as u64, (config_len as u64).saturating_sub(offset)) as usize
+            }
+            None => len,
+        }
+    }
+
+    fn with_queue<U, F>(&self, f: F) -> Option<U>
+    where
+        F: FnOnce(&QueueConfig) -> U,
+    {
+        self.queues.get(self.queue_select as usize).map(f)
+    }
+
+    fn with_queue_mut<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut QueueConfig),
+    {
+        if let Some(queue) = self.queues.get_mut(self.queue_select as usize) {
+            f(queue);
+        }
+    }
+
+    pub fn allocate_regions(
         &mut self,
         resources: &mut SystemAllocator,
     ) -> std::result::Result<Vec<(u64, u64)>, resources::Error> {
@@ -385,11 +1403,54 @@ impl VirtioMmioDevice {
         self.irq_num = irq_num;
     }
 
+    /// Gives each queue its own interrupt: used buffers of queue `i` are signaled on
+    /// `queue_irq_evts[i]` rather than on the interrupt assigned with `assign_irq`, which is still
+    /// used for configuration changes. Queues only pick up their interrupt when the driver sets
+    /// them up, so this has to be called before the guest runs.
+    ///
+    /// There must be exactly one event per queue.
+    pub fn assign_queue_irqs(&mut self, queue_irq_evts: &[IrqEdgeEvent]) -> Result<()> {
+        if queue_irq_evts.len() != self.queues.len() {
+            return Err(base::Error::new(libc::EINVAL));
+        }
+        let queue_irq_evts = queue_irq_evts
+            .iter()
+            .map(IrqEdgeEvent::try_clone)
+            .collect::<Result<Vec<_>>>()?;
+        self.queue_irq_evts = Some(queue_irq_evts);
+        Ok(())
+    }
+
+    /// Returns whether an interrupt event has been assigned with `assign_irq`. Activation only
+    /// clones the event, so it stays assigned once the device is activated.
+    pub fn has_interrupt_evt(&self) -> bool {
+        self.interrupt_evt.is_some()
+    }
+
+    /// Returns an owned copy of the interrupt event assigned with `assign_irq`.
+    pub fn interrupt_evt_clone(&self) -> Option<IrqEdgeEvent> {
+        let evt = self.interrupt_evt.as_ref()?;
+        match evt.try_clone() {
+            Ok(evt) => Some(evt),
+            Err(e) => {
+                error!(
+                    "{}: failed to clone interrupt_evt: {}",
+                    self.debug_label(),
+                    e
+                );
+                None
+            }
+        }
+    }
+
     pub fn keep_rds(&self) -> Vec<RawDescriptor> {
         let mut rds = self.device.keep_rds();
         if let Some(interrupt_evt) = &self.interrupt_evt {
             rds.extend(interrupt_evt.as_raw_descriptors());
         }
+        for queue_irq_evt in self.queue_irq_evts.iter().flatten() {
+            rds.extend(queue_irq_evt.as_raw_descriptors());
+        }
         rds
     }
 
@@ -429,6 +1490,44 @@ impl VirtioMmioDevice {
     }
 }
 
+impl std::fmt::Debug for VirtioMmioDevice {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        #[allow(dead_code)]
+        #[derive(Debug)]
+        struct QueueSummary {
+            ready: bool,
+            size: u16,
+        }
+
+        let queues: Vec<QueueSummary> = self
+            .queues
+            .iter()
+            .map(|q| QueueSummary {
+                ready: q.ready(),
+                size: q.size(),
+            })
+            .collect();
+
+        f.debug_struct("VirtioMmioDevice")
+            .field("device_type", &self.device.device_type())
+            .field("device_activated", &self.device_activated)
+            .field("driver_status", &driver_status_flags(self.driver_status))
+            .field("failed", &self.failed)
+            .field("device_feature_select", &self.device_feature_select)
+            .field("driver_feature_select", &self.driver_feature_select)
+            .field("queue_select", &self.queue_select)
+            .field("queues", &queues)
+            .field(
+                "interrupt_status",
+                &self
+                    .interrupt
+                    .as_ref()
+                    .map(Interrupt::read_interrupt_status),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
 impl Aml for VirtioMmioDevice {
     fn to_aml_bytes(&self, bytes: &mut Vec<u8>) {
         aml::Device::new(
@@ -475,11 +1574,11 @@ impl BusDevice for VirtioMmioDevice {
     }
 
     fn read(&mut self, info: BusAccessInfo, data: &mut [u8]) {
-        self.read_mmio(info, data)
+        self.read_register(info, data)
     }
 
     fn write(&mut self, info: BusAccessInfo, data: &[u8]) {
-        self.write_mmio(info, data)
+        self.write_register(info, data)
     }
 
     fn on_sandboxed(&mut self) {
@@ -487,5 +1586,1277 @@ impl BusDevice for VirtioMmioDevice {
     }
 }
 
-// TODO: Mimic the Suspendable impl in ViritoPciDevice when/if someone wants it.
-impl Suspendable for VirtioMmioDevice {}
+impl VirtioTransport for VirtioMmioDevice {
+    fn transport_type(&self) -> VirtioTransportType {
+        VirtioTransportType::Mmio
+    }
+
+    fn queue_evts(&self) -> &[Event] {
+        &self.queue_evts
+    }
+
+    fn interrupt_evt(&self) -> Option<&Event> {
+        self.interrupt_evt.as_ref().map(IrqEdgeEvent::get_trigger)
+    }
+
+    fn read_register(&self, info: BusAccessInfo, data: &mut [u8]) {
+        self.read_mmio(info, data)
+    }
+
+    fn write_register(&mut self, info: BusAccessInfo, data: &[u8]) {
+        self.write_mmio(info, data)
+    }
+
+    fn is_activated(&self) -> bool {
+        self.device_activated
+    }
+}
+
+// TODO: Mimic the snapshot/restore in VirtioPciDevice when/if someone wants it.
+impl Suspendable for VirtioMmioDevice {
+    fn sleep(&mut self) -> anyhow::Result<()> {
+        // If the device is already asleep, we should not request it to sleep again.
+        if self.sleep_state.is_some() {
+            return Ok(());
+        }
+
+        if let Some(queues) = self.device.virtio_sleep()? {
+            anyhow::ensure!(
+                self.device_activated,
+                format!(
+                    "unactivated device {} returned queues on sleep",
+                    self.debug_label()
+                ),
+            );
+            self.sleep_state = Some(SleepState::Active {
+                activated_queues: queues,
+            });
+        } else {
+            anyhow::ensure!(
+                !self.device_activated,
+                format!(
+                    "activated device {} didn't return queues on sleep",
+                    self.debug_label()
+                ),
+            );
+            self.sleep_state = Some(SleepState::Inactive);
+        }
+        Ok(())
+    }
+
+    fn wake(&mut self) -> anyhow::Result<()> {
+        match self.sleep_state.take() {
+            None => {
+                // If the device is already awake, we should not request it to wake again.
+            }
+            Some(SleepState::Inactive) => {
+                self.device.virtio_wake(None).with_context(|| {
+                    format!(
+                        "virtio_wake failed for {}, can't recover",
+                        self.debug_label(),
+                    )
+                })?;
+            }
+            Some(SleepState::Active { activated_queues }) => {
+                self.device
+                    .virtio_wake(Some((
+                        self.mem.clone(),
+                        self.interrupt
+                            .clone()
+                            .expect("interrupt missing for already active queues"),
+                        activated_queues,
+                    )))
+                    .with_context(|| {
+                        format!(
+                            "virtio_wake failed for {}, can't recover",
+                            self.debug_label(),
+                        )
+                    })?;
+            }
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use base::RawDescriptor;
+    use virtio_sys::virtio_ring::VIRTIO_RING_F_EVENT_IDX;
+
+    use super::*;
+
+    const QUEUE_SIZE: u16 = 16;
+    const QUEUE_SIZES: &[u16] = &[QUEUE_SIZE, QUEUE_SIZE];
+    const CONFIG: [u8; 2] = [0xab, 0xcd];
+    const DUMMY_FEATURE: u64 = 1 << 0;
+
+    #[derive(Default)]
+    struct DummyDevice {
+        queues: Option<BTreeMap<usize, Queue>>,
+        config_writes: Vec<(u64, Vec<u8>)>,
+        fail_activate: bool,
+        fail_reset: bool,
+        resets: usize,
+        // Reported in place of `CONFIG`, if set.
+        config_override: Option<[u8; 2]>,
+        transport_features: Option<u64>,
+        // Notification data received with `queue_notified`, as (queue index, data) pairs.
+        notifications: Vec<(usize, u32)>,
+    }
+
+    impl VirtioDevice for DummyDevice {
+        fn keep_rds(&self) -> Vec<RawDescriptor> {
+            Vec::new()
+        }
+
+        fn device_type(&self) -> DeviceType {
+            DeviceType::Rng
+        }
+
+        fn queue_max_sizes(&self) -> &[u16] {
+            QUEUE_SIZES
+        }
+
+        fn features(&self) -> u64 {
+            DUMMY_FEATURE | 1 << VIRTIO_F_VERSION_1 | 1 << VIRTIO_F_NOTIFICATION_DATA
+        }
+
+        fn set_transport_features(&mut self, features: u64) {
+            self.transport_features = Some(features);
+        }
+
+        fn config_len(&self) -> Option<usize> {
+            Some(CONFIG.len())
+        }
+
+        fn read_config(&self, offset: u64, data: &mut [u8]) {
+            let offset = offset as usize;
+            let config = self.config_override.unwrap_or(CONFIG);
+            data.copy_from_slice(&config[offset..offset + data.len()]);
+        }
+
+        fn write_config(&mut self, offset: u64, data: &[u8]) {
+            self.config_writes.push((offset, data.to_vec()));
+        }
+
+        fn activate(
+            &mut self,
+            _mem: GuestMemory,
+            _interrupt: Interrupt,
+            queues: BTreeMap<usize, Queue>,
+        ) -> anyhow::Result<()> {
+            anyhow::ensure!(!self.fail_activate, "activation failed");
+            self.queues = Some(queues);
+            Ok(())
+        }
+
+        fn queue_notified(&mut self, queue_index: usize, notify_data: u32) {
+            self.notifications.push((queue_index, notify_data));
+        }
+
+        fn reset_queue(&mut self, queue_index: usize) -> anyhow::Result<Option<Queue>> {
+            Ok(self
+                .queues
+                .as_mut()
+                .and_then(|queues| queues.remove(&queue_index)))
+        }
+
+        fn reset(&mut self) -> anyhow::Result<()> {
+            anyhow::ensure!(!self.fail_reset, "reset failed");
+            self.queues = None;
+            self.resets += 1;
+            Ok(())
+        }
+
+        fn virtio_sleep(&mut self) -> anyhow::Result<Option<BTreeMap<usize, Queue>>> {
+            Ok(self.queues.take())
+        }
+
+        fn virtio_wake(
+            &mut self,
+            queues_state: Option<(GuestMemory, Interrupt, BTreeMap<usize, Queue>)>,
+        ) -> anyhow::Result<()> {
+            self.queues = queues_state.map(|(_, _, queues)| queues);
+            Ok(())
+        }
+    }
+
+    fn new_device() -> VirtioMmioDevice {
+        let mem = GuestMemory::new(&[(GuestAddress(0), 0x10000)]).unwrap();
+        let mut dev = VirtioMmioDevice::new(mem, Box::<DummyDevice>::default(), false).unwrap();
+        dev.assign_irq(&IrqEdgeEvent::new().unwrap(), 0);
+        dev
+    }
+
+    fn access(offset: u32) -> BusAccessInfo {
+        BusAccessInfo {
+            offset: offset.into(),
+            address: offset.into(),
+            id: 0,
+        }
+    }
+
+    fn read_reg(dev: &mut VirtioMmioDevice, offset: u32) -> u32 {
+        let mut data = [0u8; 4];
+        dev.read(access(offset), &mut data);
+        u32::from_le_bytes(data)
+    }
+
+    fn write_reg(dev: &mut VirtioMmioDevice, offset: u32, val: u32) {
+        dev.write(access(offset), &val.to_le_bytes());
+    }
+
+    fn setup_queue(dev: &mut VirtioMmioDevice, index: u32, base: u32) {
+        write_reg(dev, VIRTIO_MMIO_QUEUE_SEL, index);
+        write_reg(dev, VIRTIO_MMIO_QUEUE_NUM, QUEUE_SIZE.into());
+        write_reg(dev, VIRTIO_MMIO_QUEUE_DESC_LOW, base);
+        write_reg(dev, VIRTIO_MMIO_QUEUE_AVAIL_LOW, base + 0x400);
+        write_reg(dev, VIRTIO_MMIO_QUEUE_USED_LOW, base + 0x800);
+        write_reg(dev, VIRTIO_MMIO_QUEUE_READY, 1);
+    }
+
+    fn negotiate(dev: &mut VirtioMmioDevice) {
+        let mut status = 0;
+        for bit in [
+            VIRTIO_CONFIG_S_ACKNOWLEDGE,
+            VIRTIO_CONFIG_S_DRIVER,
+            VIRTIO_CONFIG_S_FEATURES_OK,
+            VIRTIO_CONFIG_S_DRIVER_OK,
+        ] {
+            status |= bit;
+            write_reg(dev, VIRTIO_MMIO_STATUS, status);
+        }
+    }
+
+    fn activate(dev: &mut VirtioMmioDevice) {
+        setup_queue(dev, 0, 0x1000);
+        negotiate(dev);
+        assert!(dev.device_activated);
+    }
+
+    #[test]
+    fn queue_write_after_activation_ignored() {
+        let mut dev = new_device();
+        activate(&mut dev);
+
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_SEL, 0);
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_DESC_LOW, 0x8000);
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_READY, 2);
+        assert_eq!(dev.queues[0].desc_table(), GuestAddress(0x1000));
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_QUEUE_READY), 1);
+
+        // Queues the driver left unused are frozen as well.
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_SEL, 1);
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_DESC_LOW, 0x8000);
+        assert_eq!(dev.queues[1].desc_table(), GuestAddress(0));
+    }
+
+    #[test]
+    fn queue_reset_after_activation() {
+        let mut dev = new_device();
+        setup_queue(&mut dev, 0, 0x1000);
+        setup_queue(&mut dev, 1, 0x2000);
+        negotiate(&mut dev);
+        assert!(dev.device_activated);
+
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_SEL, 0);
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_READY, 0);
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_QUEUE_READY), 0);
+        assert_eq!(dev.queues[0].desc_table(), GuestAddress(0));
+        let queues = dev
+            .device_ref::<DummyDevice>()
+            .unwrap()
+            .queues
+            .as_ref()
+            .unwrap();
+        assert_eq!(queues.keys().copied().collect::<Vec<_>>(), vec![1]);
+
+        // The other queue stays with the device and the device stays activated.
+        assert!(dev.device_activated);
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_SEL, 1);
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_QUEUE_READY), 1);
+        assert_eq!(dev.queues[1].desc_table(), GuestAddress(0x2000));
+    }
+
+    #[test]
+    fn sleep_wake_preserves_activation() {
+        let mut dev = new_device();
+        activate(&mut dev);
+
+        dev.sleep().unwrap();
+        // Sleeping twice is a no-op.
+        dev.sleep().unwrap();
+        assert!(dev.device_activated);
+        dev.wake().unwrap();
+
+        assert!(dev.device_activated);
+        assert!(dev.sleep_state.is_none());
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_SEL, 0);
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_QUEUE_READY), 1);
+        assert_eq!(dev.queues[0].desc_table(), GuestAddress(0x1000));
+        assert_eq!(dev.queues[0].size(), QUEUE_SIZE);
+
+        // The device gets its queues back on wake and can hand them over again.
+        dev.sleep().unwrap();
+        assert!(matches!(dev.sleep_state, Some(SleepState::Active { .. })));
+        dev.wake().unwrap();
+    }
+
+    #[test]
+    fn sleep_wake_inactive() {
+        let mut dev = new_device();
+        dev.sleep().unwrap();
+        assert!(matches!(dev.sleep_state, Some(SleepState::Inactive)));
+        dev.wake().unwrap();
+        assert!(!dev.device_activated);
+    }
+
+    #[test]
+    fn access_stats() {
+        let mut dev = new_device();
+        assert_eq!(dev.stats(), VirtioMmioStats::default());
+
+        read_reg(&mut dev, VIRTIO_MMIO_MAGIC_VALUE);
+        read_reg(&mut dev, VIRTIO_MMIO_VERSION);
+        read_reg(&mut dev, VIRTIO_MMIO_CONFIG);
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_SEL, 1);
+        write_reg(&mut dev, VIRTIO_MMIO_CONFIG + 4, 0);
+        write_reg(&mut dev, VIRTIO_MMIO_CONFIG + 8, 0);
+
+        assert_eq!(
+            dev.stats(),
+            VirtioMmioStats {
+                control_reads: 2,
+                control_writes: 1,
+                config_reads: 1,
+                config_writes: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn debug_output() {
+        let mut dev = new_device();
+        let output = format!("{:?}", dev);
+        assert!(output.contains("device_type: Rng"), "{}", output);
+        assert!(output.contains("driver_status: []"), "{}", output);
+
+        activate(&mut dev);
+        let output = format!("{:?}", dev);
+        assert!(output.contains("DRIVER_OK"), "{}", output);
+        assert!(output.contains("ready: true, size: 16"), "{}", output);
+    }
+
+    #[test]
+    fn unsupported_width_read_zero_fills() {
+        let mut dev = new_device();
+        let mut data = [0xffu8; 2];
+        dev.read(access(VIRTIO_MMIO_MAGIC_VALUE), &mut data);
+        assert_eq!(data, [0, 0]);
+
+        // A narrow write is dropped without touching the register.
+        dev.write(access(VIRTIO_MMIO_QUEUE_SEL), &[1, 0]);
+        assert_eq!(dev.queue_select, 0);
+    }
+
+    #[test]
+    fn zero_length_access_ignored() {
+        let mut dev = new_device();
+        let mut data = [];
+        dev.read(access(VIRTIO_MMIO_MAGIC_VALUE), &mut data);
+        dev.write(access(VIRTIO_MMIO_QUEUE_SEL), &[]);
+        dev.write(access(VIRTIO_MMIO_CONFIG), &[]);
+
+        assert_eq!(dev.queue_select, 0);
+        assert_eq!(dev.stats(), VirtioMmioStats::default());
+        assert!(dev
+            .device_ref::<DummyDevice>()
+            .unwrap()
+            .config_writes
+            .is_empty());
+    }
+
+    #[test]
+    fn queue_ready_clear_resets_queue() {
+        let mut dev = new_device();
+        setup_queue(&mut dev, 0, 0x1000);
+        setup_queue(&mut dev, 1, 0x2000);
+
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_SEL, 0);
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_NUM, 8);
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_READY, 0);
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_QUEUE_READY), 0);
+        assert_eq!(dev.queues[0].desc_table(), GuestAddress(0));
+        assert_eq!(dev.queues[0].avail_ring(), GuestAddress(0));
+        assert_eq!(dev.queues[0].used_ring(), GuestAddress(0));
+        assert_eq!(dev.queues[0].size(), QUEUE_SIZE);
+
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_SEL, 1);
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_QUEUE_READY), 1);
+        assert_eq!(dev.queues[1].desc_table(), GuestAddress(0x2000));
+        assert_eq!(dev.queues[1].avail_ring(), GuestAddress(0x2400));
+        assert_eq!(dev.queues[1].used_ring(), GuestAddress(0x2800));
+    }
+
+    #[test]
+    fn transport_trait_object() {
+        let mut transport: Box<dyn VirtioTransport> = Box::new(new_device());
+        assert_eq!(transport.transport_type(), VirtioTransportType::Mmio);
+        assert_eq!(transport.queue_evts().len(), QUEUE_SIZES.len());
+        assert!(transport.interrupt_evt().is_some());
+        assert!(!transport.is_activated());
+
+        let mut data = [0u8; 4];
+        transport.read_register(access(VIRTIO_MMIO_MAGIC_VALUE), &mut data);
+        assert_eq!(u32::from_le_bytes(data), VIRT_MAGIC);
+
+        transport.write_register(access(VIRTIO_MMIO_QUEUE_SEL), &1u32.to_le_bytes());
+        transport.read_register(access(VIRTIO_MMIO_QUEUE_NUM_MAX), &mut data);
+        assert_eq!(u32::from_le_bytes(data), u32::from(QUEUE_SIZE));
+    }
+
+    #[test]
+    fn config_read_past_end_zero_fills() {
+        let mut dev = new_device();
+        assert_eq!(
+            read_reg(&mut dev, VIRTIO_MMIO_CONFIG).to_le_bytes(),
+            [0xab, 0xcd, 0, 0]
+        );
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_CONFIG + 4), 0);
+    }
+
+    #[test]
+    fn activate_callback_runs_once() {
+        let mut dev = new_device();
+        let calls = Arc::new(AtomicU64::new(0));
+        let num_evts = Arc::new(AtomicU64::new(0));
+        let (calls_cb, num_evts_cb) = (calls.clone(), num_evts.clone());
+        dev.on_activate(Box::new(move |evts| {
+            calls_cb.fetch_add(1, Ordering::SeqCst);
+            num_evts_cb.store(evts.len() as u64, Ordering::SeqCst);
+        }));
+
+        activate(&mut dev);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(num_evts.load(Ordering::SeqCst), QUEUE_SIZES.len() as u64);
+
+        write_reg(&mut dev, VIRTIO_MMIO_STATUS, DEVICE_RESET);
+        activate(&mut dev);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn misaligned_desc_table_refuses_activation() {
+        let mut dev = new_device();
+        setup_queue(&mut dev, 0, 0x1008);
+        negotiate(&mut dev);
+        assert!(!dev.device_activated);
+    }
+
+    #[test]
+    fn signal_used_queue_raises_interrupt() {
+        let mut dev = new_device();
+        let irq_evt = IrqEdgeEvent::new().unwrap();
+        dev.assign_irq(&irq_evt, 0);
+        assert!(dev.signal_used_queue().is_err());
+
+        activate(&mut dev);
+        dev.signal_used_queue().unwrap();
+        assert_eq!(
+            irq_evt
+                .get_trigger()
+                .wait_timeout(std::time::Duration::ZERO)
+                .unwrap(),
+            base::EventWaitResult::Signaled
+        );
+        assert_eq!(
+            read_reg(&mut dev, VIRTIO_MMIO_INTERRUPT_STATUS),
+            u32::from(InterruptStatus::VRING)
+        );
+    }
+
+    #[test]
+    fn queue_counts() {
+        let dev = new_device();
+        assert_eq!(dev.num_queues(), QUEUE_SIZES.len());
+        for (index, &size) in QUEUE_SIZES.iter().enumerate() {
+            assert_eq!(dev.queue_max_size(index), Some(size));
+        }
+        assert_eq!(dev.queue_max_size(QUEUE_SIZES.len()), None);
+    }
+
+    #[test]
+    fn configured_version() {
+        let mem = GuestMemory::new(&[(GuestAddress(0), 0x10000)]).unwrap();
+        let mut dev =
+            VirtioMmioDevice::with_version(mem.clone(), Box::<DummyDevice>::default(), false, 1)
+                .unwrap();
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_VERSION), 1);
+
+        let mut dev = new_device();
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_VERSION), 2);
+
+        assert!(
+            VirtioMmioDevice::with_version(mem, Box::<DummyDevice>::default(), false, 3).is_err()
+        );
+    }
+
+    #[test]
+    fn feature_ack_after_features_ok_ignored() {
+        let mut dev = new_device();
+        let queue_acked_features = |dev: &VirtioMmioDevice| dev.queues[0].acked_features();
+
+        write_reg(&mut dev, VIRTIO_MMIO_DRIVER_FEATURES_SEL, 1);
+        write_reg(&mut dev, VIRTIO_MMIO_DRIVER_FEATURES, 1);
+        assert_eq!(queue_acked_features(&dev), 1 << VIRTIO_F_VERSION_1);
+
+        write_reg(
+            &mut dev,
+            VIRTIO_MMIO_STATUS,
+            VIRTIO_CONFIG_S_ACKNOWLEDGE | VIRTIO_CONFIG_S_DRIVER | VIRTIO_CONFIG_S_FEATURES_OK,
+        );
+        write_reg(&mut dev, VIRTIO_MMIO_DRIVER_FEATURES_SEL, 0);
+        write_reg(&mut dev, VIRTIO_MMIO_DRIVER_FEATURES, DUMMY_FEATURE as u32);
+        assert_eq!(queue_acked_features(&dev), 1 << VIRTIO_F_VERSION_1);
+    }
+
+    #[test]
+    fn downcast_inner_device() {
+        let mut dev = new_device();
+        assert!(dev.device_ref::<DummyDevice>().is_some());
+        assert!(dev.device_ref::<Rng>().is_none());
+
+        activate(&mut dev);
+        let inner = dev.device_mut::<DummyDevice>().unwrap();
+        assert!(inner.queues.is_some());
+    }
+
+    #[test]
+    fn out_of_range_feature_select() {
+        let mut dev = new_device();
+        write_reg(&mut dev, VIRTIO_MMIO_DEVICE_FEATURES_SEL, 5);
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_DEVICE_FEATURES), 0);
+
+        write_reg(&mut dev, VIRTIO_MMIO_DRIVER_FEATURES_SEL, 5);
+        write_reg(&mut dev, VIRTIO_MMIO_DRIVER_FEATURES, u32::MAX);
+        assert_eq!(dev.queues[0].acked_features(), 0);
+    }
+
+    #[test]
+    fn notify_layout() {
+        let mut dev = new_device();
+        let signaled = |evt: &Event| {
+            evt.wait_timeout(std::time::Duration::ZERO).unwrap() == base::EventWaitResult::Signaled
+        };
+        assert!(dev.set_notify_layout(0x180, 0).is_err());
+        assert!(dev.set_notify_layout(0x1f8, 8).is_err());
+
+        dev.set_notify_layout(0x180, 8).unwrap();
+        activate(&mut dev);
+        write_reg(&mut dev, 0x180 + 8, 0);
+        assert!(signaled(&dev.queue_evts[1]));
+        assert!(!signaled(&dev.queue_evts[0]));
+        assert_eq!(dev.notify_count(1), Some(1));
+
+        // Anywhere within a queue's slot notifies it.
+        write_reg(&mut dev, 0x180 + 4, 1);
+        assert!(signaled(&dev.queue_evts[0]));
+        assert!(!signaled(&dev.queue_evts[1]));
+
+        // Past the last queue is no longer part of the layout.
+        write_reg(&mut dev, 0x180 + 2 * 8, 0);
+        assert!(!signaled(&dev.queue_evts[0]));
+        assert!(!signaled(&dev.queue_evts[1]));
+
+        // The QueueNotify register still works.
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_NOTIFY, 1);
+        assert!(signaled(&dev.queue_evts[1]));
+
+        let addrs: Vec<u64> = dev.ioevents().iter().map(|(_, addr, _)| *addr).collect();
+        assert_eq!(addrs, vec![0x180, 0x188]);
+    }
+
+    #[test]
+    fn kick_all_queues() {
+        let mut dev = new_device();
+        let signaled = |evt: &Event| {
+            evt.wait_timeout(std::time::Duration::ZERO).unwrap() == base::EventWaitResult::Signaled
+        };
+        assert!(dev.kick_all_queues().is_err());
+
+        // Only queue 0 is set up by `activate`.
+        activate(&mut dev);
+        dev.kick_all_queues().unwrap();
+        assert!(signaled(&dev.queue_evts[0]));
+        assert!(!signaled(&dev.queue_evts[0]));
+        assert!(!signaled(&dev.queue_evts[1]));
+        assert_eq!(dev.notify_count(0), Some(0));
+    }
+
+    #[test]
+    fn device_failed_stops_device() {
+        let mut dev = new_device();
+        activate(&mut dev);
+        let queue_evt = dev.queue_evts[0].try_clone().unwrap();
+
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_NOTIFY, 0);
+        assert_eq!(
+            queue_evt.wait_timeout(std::time::Duration::ZERO).unwrap(),
+            base::EventWaitResult::Signaled
+        );
+
+        let status = read_reg(&mut dev, VIRTIO_MMIO_STATUS);
+        write_reg(
+            &mut dev,
+            VIRTIO_MMIO_STATUS,
+            status | VIRTIO_CONFIG_S_FAILED,
+        );
+        assert!(!dev.device_activated);
+        assert!(dev.device_ref::<DummyDevice>().unwrap().queues.is_none());
+        assert!(!dev.queues[0].ready());
+
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_NOTIFY, 0);
+        assert_eq!(
+            queue_evt.wait_timeout(std::time::Duration::ZERO).unwrap(),
+            base::EventWaitResult::TimedOut
+        );
+    }
+
+    #[test]
+    fn mark_failed_sets_status_bit() {
+        let mut dev = new_device();
+        activate(&mut dev);
+        assert!(!dev.is_failed());
+        let status = read_reg(&mut dev, VIRTIO_MMIO_STATUS);
+        assert_eq!(status & VIRTIO_CONFIG_S_FAILED, 0);
+
+        dev.mark_failed();
+        assert!(dev.is_failed());
+        assert_eq!(
+            read_reg(&mut dev, VIRTIO_MMIO_STATUS),
+            status | VIRTIO_CONFIG_S_FAILED
+        );
+
+        write_reg(&mut dev, VIRTIO_MMIO_STATUS, DEVICE_RESET);
+        assert!(!dev.is_failed());
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_STATUS), 0);
+    }
+
+    #[test]
+    fn bump_config_generation() {
+        let mut dev = new_device();
+        activate(&mut dev);
+        let generation = read_reg(&mut dev, VIRTIO_MMIO_CONFIG_GENERATION);
+        dev.bump_config_generation();
+        assert_eq!(
+            read_reg(&mut dev, VIRTIO_MMIO_CONFIG_GENERATION),
+            generation + 1
+        );
+    }
+
+    #[test]
+    fn config_reads_coherent_within_generation() {
+        let mut dev = new_device();
+        activate(&mut dev);
+        let config = u32::from(u16::from_le_bytes(CONFIG));
+
+        let generation = read_reg(&mut dev, VIRTIO_MMIO_CONFIG_GENERATION);
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_CONFIG), config);
+        // The device changes its config in the middle of the driver's read loop.
+        dev.device_mut::<DummyDevice>().unwrap().config_override = Some([0x12, 0x34]);
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_CONFIG), config);
+        assert_eq!(
+            read_reg(&mut dev, VIRTIO_MMIO_CONFIG_GENERATION),
+            generation
+        );
+
+        // Once the generation changes the driver retries and sees the new config.
+        dev.bump_config_generation();
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_CONFIG), 0x3412);
+        let generation = read_reg(&mut dev, VIRTIO_MMIO_CONFIG_GENERATION);
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_CONFIG), 0x3412);
+        assert_eq!(
+            read_reg(&mut dev, VIRTIO_MMIO_CONFIG_GENERATION),
+            generation
+        );
+    }
+
+    #[test]
+    fn device_config_change_moves_generation() {
+        let mut dev = new_device();
+        activate(&mut dev);
+
+        let generation = read_reg(&mut dev, VIRTIO_MMIO_CONFIG_GENERATION);
+        assert_eq!(
+            read_reg(&mut dev, VIRTIO_MMIO_CONFIG),
+            u32::from(u16::from_le_bytes(CONFIG))
+        );
+        // The device changes its config on its own and tells the driver.
+        dev.device_mut::<DummyDevice>().unwrap().config_override = Some([0x12, 0x34]);
+        dev.interrupt.as_ref().unwrap().signal_config_changed();
+
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_CONFIG), 0x3412);
+        assert_eq!(
+            read_reg(&mut dev, VIRTIO_MMIO_CONFIG_GENERATION),
+            generation + 1
+        );
+        assert_eq!(
+            read_reg(&mut dev, VIRTIO_MMIO_CONFIG_GENERATION),
+            generation + 1
+        );
+    }
+
+    #[test]
+    fn notify_with_notification_data() {
+        let mut dev = new_device();
+        let queue_evts: Vec<Event> = dev
+            .queue_evts
+            .iter()
+            .map(|evt| evt.try_clone().unwrap())
+            .collect();
+        let signaled = |evt: &Event| {
+            evt.wait_timeout(std::time::Duration::ZERO).unwrap() == base::EventWaitResult::Signaled
+        };
+
+        // Without the feature the whole value is the queue index.
+        activate(&mut dev);
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_NOTIFY, 0x0005_0001);
+        assert!(!signaled(&queue_evts[1]));
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_NOTIFY, 1);
+        assert!(signaled(&queue_evts[1]));
+        assert_eq!(dev.notify_data(1), None);
+
+        write_reg(&mut dev, VIRTIO_MMIO_STATUS, 0);
+        write_reg(&mut dev, VIRTIO_MMIO_DRIVER_FEATURES_SEL, 1);
+        write_reg(
+            &mut dev,
+            VIRTIO_MMIO_DRIVER_FEATURES,
+            1 << (VIRTIO_F_NOTIFICATION_DATA - 32),
+        );
+        activate(&mut dev);
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_NOTIFY, 0x8005_0001);
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_NOTIFY, 0x8006_0001);
+        assert!(signaled(&queue_evts[1]));
+        assert!(!signaled(&queue_evts[0]));
+        assert_eq!(dev.notify_data(1), Some(0x8006));
+        assert_eq!(dev.notify_data(0), None);
+        // The device receives the data of every notification.
+        assert_eq!(
+            dev.device_ref::<DummyDevice>().unwrap().notifications,
+            vec![(1, 0x8005), (1, 0x8006)]
+        );
+    }
+
+    #[test]
+    fn from_parts_restores_registers() {
+        let features = DummyDevice::default().features();
+        let mut queues: Vec<QueueConfig> = QUEUE_SIZES
+            .iter()
+            .map(|&size| QueueConfig::new(size, features))
+            .collect();
+        queues[1].set_size(8);
+        queues[1].set_desc_table(GuestAddress(0x2000));
+        queues[1].set_avail_ring(GuestAddress(0x2400));
+        queues[1].set_used_ring(GuestAddress(0x2800));
+        queues[1].ack_features(1 << VIRTIO_F_VERSION_1);
+        queues[1].set_ready(true);
+        let status =
+            VIRTIO_CONFIG_S_ACKNOWLEDGE | VIRTIO_CONFIG_S_DRIVER | VIRTIO_CONFIG_S_FEATURES_OK;
+
+        let mem = GuestMemory::new(&[(GuestAddress(0), 0x10000)]).unwrap();
+        let mut dev = VirtioMmioDevice::from_parts(
+            mem,
+            Box::<DummyDevice>::default(),
+            false,
+            queues,
+            status as u8,
+        )
+        .unwrap();
+        dev.assign_irq(&IrqEdgeEvent::new().unwrap(), 0);
+
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_STATUS), status);
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_SEL, 1);
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_QUEUE_READY), 1);
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_SEL, 0);
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_QUEUE_READY), 0);
+        assert_eq!(
+            dev.queue_config(1),
+            Some(VirtioMmioQueueInfo {
+                desc_table: GuestAddress(0x2000),
+                avail_ring: GuestAddress(0x2400),
+                used_ring: GuestAddress(0x2800),
+                size: 8,
+                ready: true,
+            })
+        );
+
+        // Finishing the handshake activates the device with the restored queue.
+        write_reg(
+            &mut dev,
+            VIRTIO_MMIO_STATUS,
+            status | VIRTIO_CONFIG_S_DRIVER_OK,
+        );
+        assert!(dev.device_activated);
+        let inner = dev.device_ref::<DummyDevice>().unwrap();
+        assert_eq!(inner.queues.as_ref().unwrap()[&1].size(), 8);
+    }
+
+    #[test]
+    fn from_parts_rejects_mismatched_queues() {
+        let mem = GuestMemory::new(&[(GuestAddress(0), 0x10000)]).unwrap();
+        let queues = vec![QueueConfig::new(QUEUE_SIZE, 0)];
+        assert!(
+            VirtioMmioDevice::from_parts(mem, Box::<DummyDevice>::default(), false, queues, 0)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn from_parts_rejects_driver_ok() {
+        let features = DummyDevice::default().features();
+        let queues: Vec<QueueConfig> = QUEUE_SIZES
+            .iter()
+            .map(|&size| QueueConfig::new(size, features))
+            .collect();
+        let status = VIRTIO_CONFIG_S_ACKNOWLEDGE
+            | VIRTIO_CONFIG_S_DRIVER
+            | VIRTIO_CONFIG_S_FEATURES_OK
+            | VIRTIO_CONFIG_S_DRIVER_OK;
+        let mem = GuestMemory::new(&[(GuestAddress(0), 0x10000)]).unwrap();
+        assert!(VirtioMmioDevice::from_parts(
+            mem,
+            Box::<DummyDevice>::default(),
+            false,
+            queues,
+            status as u8,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn config_write_past_end_dropped() {
+        let mut dev = new_device();
+        write_reg(&mut dev, VIRTIO_MMIO_CONFIG, 0x04030201);
+        write_reg(&mut dev, VIRTIO_MMIO_CONFIG + 4, 0x08070605);
+        assert_eq!(
+            dev.device_ref::<DummyDevice>().unwrap().config_writes,
+            vec![(0, vec![0x01, 0x02])]
+        );
+    }
+
+    #[test]
+    fn driver_status_transitions() {
+        let negotiated = (VIRTIO_CONFIG_S_ACKNOWLEDGE
+            | VIRTIO_CONFIG_S_DRIVER
+            | VIRTIO_CONFIG_S_FEATURES_OK) as u8;
+        let ready = negotiated | VIRTIO_CONFIG_S_DRIVER_OK as u8;
+        assert_eq!(
+            driver_status_changes(negotiated, ready),
+            (vec!["DRIVER_OK"], vec![])
+        );
+        assert_eq!(
+            driver_status_changes(ready, 0),
+            (
+                vec![],
+                vec!["ACKNOWLEDGE", "DRIVER", "DRIVER_OK", "FEATURES_OK"]
+            )
+        );
+        assert_eq!(driver_status_changes(ready, ready), (vec![], vec![]));
+    }
+
+    #[test]
+    fn register_names() {
+        assert_eq!(reg_name(VIRTIO_MMIO_MAGIC_VALUE.into()), "MagicValue");
+        assert_eq!(reg_name(VIRTIO_MMIO_QUEUE_SEL.into()), "QueueSel");
+        assert_eq!(reg_name(0x18), "unknown control register");
+        assert_eq!(reg_name(u64::from(VIRTIO_MMIO_CONFIG) + 8), "Config");
+    }
+
+    #[test]
+    fn out_of_range_queue_select() {
+        let mut dev = new_device();
+        let num_queues = QUEUE_SIZES.len() as u32;
+
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_SEL, num_queues);
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_QUEUE_NUM_MAX), 0);
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_QUEUE_READY), 0);
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_NUM, 4);
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_READY, 1);
+        assert!(dev.queues.iter().all(|q| !q.ready()));
+
+        // Only the low 16 bits would select queue 0.
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_SEL, 0x1_0000);
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_QUEUE_NUM_MAX), 0);
+
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_SEL, 0);
+        assert_eq!(
+            read_reg(&mut dev, VIRTIO_MMIO_QUEUE_NUM_MAX),
+            u32::from(QUEUE_SIZES[0])
+        );
+    }
+
+    #[test]
+    fn read_only_register_write_ignored() {
+        assert!(is_read_only_reg(VIRTIO_MMIO_VERSION.into()));
+        assert!(is_read_only_reg(VIRTIO_MMIO_CONFIG_GENERATION.into()));
+        assert!(!is_read_only_reg(VIRTIO_MMIO_STATUS.into()));
+        assert!(!is_read_only_reg(0x18));
+        assert!(!is_read_only_reg(VIRTIO_MMIO_CONFIG.into()));
+
+        let mut dev = new_device();
+        write_reg(&mut dev, VIRTIO_MMIO_VERSION, 1);
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_VERSION), 2);
+        write_reg(&mut dev, VIRTIO_MMIO_MAGIC_VALUE, 0);
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_MAGIC_VALUE), VIRT_MAGIC);
+    }
+
+    #[test]
+    fn failed_activation_can_be_retried() {
+        let mut dev = new_device();
+        dev.device_mut::<DummyDevice>().unwrap().fail_activate = true;
+        setup_queue(&mut dev, 0, 0x1000);
+        negotiate(&mut dev);
+        assert!(!dev.device_activated);
+        assert!(dev.interrupt.is_none());
+        assert_ne!(
+            read_reg(&mut dev, VIRTIO_MMIO_STATUS) & VIRTIO_CONFIG_S_NEEDS_RESET,
+            0
+        );
+
+        dev.device_mut::<DummyDevice>().unwrap().fail_activate = false;
+        negotiate(&mut dev);
+        assert!(dev.device_activated);
+        assert!(dev.interrupt.is_some());
+        let inner = dev.device_ref::<DummyDevice>().unwrap();
+        assert_eq!(inner.queues.as_ref().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn queue_config_reads_back_rings() {
+        let mut dev = new_device();
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_SEL, 1);
+        write_reg(&mut dev, VIRTIO_MMIO_QUEUE_DESC_HIGH, 1);
+        setup_queue(&mut dev, 1, 0x3000);
+
+        assert_eq!(
+            dev.queue_config(1),
+            Some(VirtioMmioQueueInfo {
+                desc_table: GuestAddress(0x1_0000_3000),
+                avail_ring: GuestAddress(0x3400),
+                used_ring: GuestAddress(0x3800),
+                size: QUEUE_SIZE,
+                ready: true,
+            })
+        );
+        assert!(!dev.queue_config(0).unwrap().ready);
+        assert_eq!(dev.queue_config(2), None);
+    }
+
+    #[test]
+    fn dirty_regions_cover_rings() {
+        let mut dev = new_device().with_memory_access_tracker(MemoryAccessTracker::new());
+        setup_queue(&mut dev, 1, 0x3000);
+        assert!(dev.dirty_regions().is_empty());
+
+        activate(&mut dev);
+        let size = usize::from(QUEUE_SIZE);
+        assert_eq!(
+            dev.dirty_regions(),
+            vec![
+                (GuestAddress(0x1000), 16 * size),
+                (GuestAddress(0x1400), 6 + 2 * size),
+                (GuestAddress(0x1800), 6 + 8 * size),
+                (GuestAddress(0x3000), 16 * size),
+                (GuestAddress(0x3400), 6 + 2 * size),
+                (GuestAddress(0x3800), 6 + 8 * size),
+            ]
+        );
+
+        // Activating again after a reset does not record the same rings twice.
+        write_reg(&mut dev, VIRTIO_MMIO_STATUS, DEVICE_RESET);
+        activate(&mut dev);
+        assert_eq!(dev.dirty_regions().len(), 6);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "activate entered while already activating")]
+    fn reentrant_activation_panics() {
+        let mut dev = new_device();
+        // As if another activation of the same device were still running.
+        dev.activating.store(true, Ordering::SeqCst);
+        setup_queue(&mut dev, 0, 0x1000);
+        negotiate(&mut dev);
+    }
+
+    #[test]
+    fn per_queue_interrupts() {
+        let mut dev = new_device();
+        let shared_irq = dev.interrupt_evt_clone().unwrap();
+        let queue_irqs: Vec<IrqEdgeEvent> = QUEUE_SIZES
+            .iter()
+            .map(|_| IrqEdgeEvent::new().unwrap())
+            .collect();
+        assert!(dev.assign_queue_irqs(&queue_irqs[1..]).is_err());
+        dev.assign_queue_irqs(&queue_irqs).unwrap();
+
+        setup_queue(&mut dev, 0, 0x1000);
+        setup_queue(&mut dev, 1, 0x2000);
+        negotiate(&mut dev);
+        assert!(dev.device_activated);
+
+        let vector = dev
+            .device_ref::<DummyDevice>()
+            .unwrap()
+            .queues
+            .as_ref()
+            .unwrap()[&1]
+            .vector();
+        dev.interrupt.as_ref().unwrap().signal_used_queue(vector);
+        let signaled = |irq: &IrqEdgeEvent| {
+            irq.get_trigger()
+                .wait_timeout(std::time::Duration::ZERO)
+                .unwrap()
+                == base::EventWaitResult::Signaled
+        };
+        assert!(signaled(&queue_irqs[1]));
+        assert!(!signaled(&queue_irqs[0]));
+        assert!(!signaled(&shared_irq));
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_INTERRUPT_STATUS), 0);
+
+        // Configuration changes still go through the shared interrupt.
+        dev.interrupt.as_ref().unwrap().signal_config_changed();
+        assert!(signaled(&shared_irq));
+        assert!(!signaled(&queue_irqs[1]));
+    }
+
+    #[test]
+    fn has_interrupt_evt() {
+        let mem = GuestMemory::new(&[(GuestAddress(0), 0x10000)]).unwrap();
+        let mut dev = VirtioMmioDevice::new(mem, Box::<DummyDevice>::default(), false).unwrap();
+        assert!(!dev.has_interrupt_evt());
+
+        dev.assign_irq(&IrqEdgeEvent::new().unwrap(), 0);
+        assert!(dev.has_interrupt_evt());
+        activate(&mut dev);
+        assert!(dev.has_interrupt_evt());
+    }
+
+    #[test]
+    fn interrupt_evt_clone_survives_activation() {
+        let mut dev = new_device();
+        let irq_evt = dev.interrupt_evt_clone().unwrap();
+
+        activate(&mut dev);
+        dev.signal_used_queue().unwrap();
+        assert_eq!(
+            irq_evt
+                .get_trigger()
+                .wait_timeout(std::time::Duration::ZERO)
+                .unwrap(),
+            base::EventWaitResult::Signaled
+        );
+    }
+
+    #[test]
+    fn queue_notify_count() {
+        let mut dev = new_device();
+        activate(&mut dev);
+        for _ in 0..3 {
+            write_reg(&mut dev, VIRTIO_MMIO_QUEUE_NOTIFY, 0);
+        }
+        assert_eq!(dev.notify_count(0), Some(3));
+        assert_eq!(dev.notify_count(1), Some(0));
+        assert_eq!(dev.notify_count(2), None);
+    }
+
+    #[test]
+    fn identity_matches_registers() {
+        let mut dev = new_device();
+        assert_eq!(
+            dev.identity(),
+            VirtioMmioIdentity {
+                magic: read_reg(&mut dev, VIRTIO_MMIO_MAGIC_VALUE),
+                version: read_reg(&mut dev, VIRTIO_MMIO_VERSION),
+                device_id: read_reg(&mut dev, VIRTIO_MMIO_DEVICE_ID),
+                vendor_id: read_reg(&mut dev, VIRTIO_MMIO_VENDOR_ID),
+            }
+        );
+        assert_eq!(dev.identity().device_id, DeviceType::Rng as u32);
+    }
+
+    #[test]
+    fn configured_vendor_id() {
+        let mut dev = new_device();
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_VENDOR_ID), VIRT_VENDOR);
+
+        let mut dev = new_device().with_vendor_id(0x1af4);
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_VENDOR_ID), 0x1af4);
+    }
+
+    #[test]
+    fn batch_bring_up() {
+        let mut dev = new_device();
+        let ready_status = VIRTIO_CONFIG_S_ACKNOWLEDGE
+            | VIRTIO_CONFIG_S_DRIVER
+            | VIRTIO_CONFIG_S_FEATURES_OK
+            | VIRTIO_CONFIG_S_DRIVER_OK;
+        let reg = |offset: u32| u64::from(offset);
+        dev.apply_writes(&[
+            (reg(VIRTIO_MMIO_STATUS), 0),
+            (reg(VIRTIO_MMIO_STATUS), VIRTIO_CONFIG_S_ACKNOWLEDGE),
+            (
+                reg(VIRTIO_MMIO_STATUS),
+                VIRTIO_CONFIG_S_ACKNOWLEDGE | VIRTIO_CONFIG_S_DRIVER,
+            ),
+            (reg(VIRTIO_MMIO_DRIVER_FEATURES_SEL), 1),
+            (reg(VIRTIO_MMIO_DRIVER_FEATURES), 1),
+            (
+                reg(VIRTIO_MMIO_STATUS),
+                VIRTIO_CONFIG_S_ACKNOWLEDGE | VIRTIO_CONFIG_S_DRIVER | VIRTIO_CONFIG_S_FEATURES_OK,
+            ),
+            (reg(VIRTIO_MMIO_QUEUE_SEL), 0),
+            (reg(VIRTIO_MMIO_QUEUE_NUM), QUEUE_SIZE.into()),
+            (reg(VIRTIO_MMIO_QUEUE_DESC_LOW), 0x1000),
+            (reg(VIRTIO_MMIO_QUEUE_AVAIL_LOW), 0x1400),
+            (reg(VIRTIO_MMIO_QUEUE_USED_LOW), 0x1800),
+            (reg(VIRTIO_MMIO_QUEUE_READY), 1),
+            (reg(VIRTIO_MMIO_STATUS), ready_status),
+        ]);
+        assert!(dev.device_activated);
+
+        assert_eq!(
+            dev.read_batch(&[
+                reg(VIRTIO_MMIO_MAGIC_VALUE),
+                reg(VIRTIO_MMIO_DEVICE_ID),
+                reg(VIRTIO_MMIO_QUEUE_READY),
+                reg(VIRTIO_MMIO_STATUS),
+            ]),
+            vec![VIRT_MAGIC, DeviceType::Rng as u32, 1, ready_status]
+        );
+    }
+
+    #[test]
+    fn device_type_override() {
+        let mut dev = new_device();
+        assert_eq!(
+            read_reg(&mut dev, VIRTIO_MMIO_DEVICE_ID),
+            DeviceType::Rng as u32
+        );
+
+        let mut dev = new_device().with_device_type_override(DeviceType::Console as u32);
+        assert_eq!(
+            read_reg(&mut dev, VIRTIO_MMIO_DEVICE_ID),
+            DeviceType::Console as u32
+        );
+        assert_eq!(dev.device.device_type(), DeviceType::Rng);
+        assert_eq!(dev.identity().device_id, DeviceType::Console as u32);
+    }
+
+    #[test]
+    fn status_zero_resets_device() {
+        let mut dev = new_device();
+        activate(&mut dev);
+        assert_eq!(dev.device_ref::<DummyDevice>().unwrap().resets, 0);
+
+        write_reg(&mut dev, VIRTIO_MMIO_STATUS, DEVICE_RESET);
+        assert!(!dev.device_activated);
+        assert_eq!(dev.device_ref::<DummyDevice>().unwrap().resets, 1);
+        assert_eq!(read_reg(&mut dev, VIRTIO_MMIO_QUEUE_READY), 0);
+    }
+
+    #[test]
+    fn transport_features() {
+        let event_idx = 1 << VIRTIO_RING_F_EVENT_IDX;
+        let mut dev = new_device().with_transport_features(event_idx);
+        assert_eq!(
+            read_reg(&mut dev, VIRTIO_MMIO_DEVICE_FEATURES),
+            (DummyDevice::default().features() | event_idx) as u32
+        );
+
+        write_reg(
+            &mut dev,
+            VIRTIO_MMIO_DRIVER_FEATURES,
+            (DUMMY_FEATURE | event_idx) as u32,
+        );
+        assert_eq!(dev.queues[0].acked_features(), DUMMY_FEATURE | event_idx);
+        assert_eq!(
+            dev.device_ref::<DummyDevice>().unwrap().transport_features,
+            None
+        );
+        activate(&mut dev);
+        assert_eq!(
+            dev.device_ref::<DummyDevice>().unwrap().transport_features,
+            Some(event_idx)
+        );
+    }
+
+    #[test]
+    fn strict_mode_fails_device() {
+        let failed = |dev: &mut VirtioMmioDevice| {
+            read_reg(dev, VIRTIO_MMIO_STATUS) & VIRTIO_CONFIG_S_FAILED != 0
+        };
+
+        let mut dev = new_device();
+        write_reg(&mut dev, VIRTIO_MMIO_DEVICE_FEATURES, 0);
+        assert!(!failed(&mut dev));
+
+        dev.set_strict(true);
+        write_reg(&mut dev, VIRTIO_MMIO_DEVICE_FEATURES, 0);
+        assert!(failed(&mut dev));
+
+        // Resetting the device clears the failure.
+        write_reg(&mut dev, VIRTIO_MMIO_STATUS, DEVICE_RESET);
+        assert!(!failed(&mut dev));
+        dev.write(access(VIRTIO_MMIO_STATUS), &[0]);
+        assert!(failed(&mut dev));
+    }
+
+    #[test]
+    fn strict_mode_allows_narrow_config_accesses() {
+        let mut dev = new_device();
+        dev.set_strict(true);
+
+        let mut data = [0u8; 1];
+        dev.read(access(VIRTIO_MMIO_CONFIG + 1), &mut data);
+        assert_eq!(data, [CONFIG[1]]);
+        dev.write(access(VIRTIO_MMIO_CONFIG), &[0x12, 0x34]);
+        assert_eq!(
+            dev.device_mut::<DummyDevice>().unwrap().config_writes,
+            vec![(0, vec![0x12, 0x34])]
+        );
+        assert!(!dev.is_failed());
+    }
+
+    #[test]
+    fn failed_device_not_activated() {
+        let mut dev = new_device();
+        dev.mark_failed();
+        setup_queue(&mut dev, 0, 0x1000);
+        negotiate(&mut dev);
+        assert!(!dev.device_activated);
+
+        write_reg(&mut dev, VIRTIO_MMIO_STATUS, DEVICE_RESET);
+        activate(&mut dev);
+    }
+
+    #[test]
+    fn reset_count() {
+        let mut dev = new_device();
+        assert_eq!(dev.reset_count(), 0);
+        for _ in 0..2 {
+            activate(&mut dev);
+            write_reg(&mut dev, VIRTIO_MMIO_STATUS, DEVICE_RESET);
+        }
+        assert_eq!(dev.reset_count(), 2);
+
+        // Failed resets aren't counted.
+        activate(&mut dev);
+        dev.device_mut::<DummyDevice>().unwrap().fail_reset = true;
+        write_reg(&mut dev, VIRTIO_MMIO_STATUS, DEVICE_RESET);
+        assert!(dev.device_activated);
+        assert_eq!(dev.reset_count(), 2);
+    }
+}
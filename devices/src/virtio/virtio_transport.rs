@@ -0,0 +1,33 @@
+// Copyright 2026 The ChromiumOS Authors
+// Use of this source code is governed by a BSD-style license that can be
+// found in the LICENSE file.
+
+use base::Event;
+
+use super::VirtioTransportType;
+use crate::BusAccessInfo;
+use crate::BusDevice;
+
+/// The guest-facing side of a virtio device, independent of how it is attached to the guest.
+///
+/// This allows code that wires up virtio devices to handle the different transports uniformly.
+pub trait VirtioTransport: BusDevice {
+    /// Returns the kind of transport this is.
+    fn transport_type(&self) -> VirtioTransportType;
+
+    /// Returns the events signaled by the guest when it notifies each queue, indexed by queue.
+    fn queue_evts(&self) -> &[Event];
+
+    /// Returns the event signaled to inject interrupts into the guest, if one has been assigned.
+    /// This is the trigger of the transport's edge or level triggered interrupt.
+    fn interrupt_evt(&self) -> Option<&Event>;
+
+    /// Reads from the transport's register space at `info.offset` into `data`.
+    fn read_register(&self, info: BusAccessInfo, data: &mut [u8]);
+
+    /// Writes `data` to the transport's register space at `info.offset`.
+    fn write_register(&mut self, info: BusAccessInfo, data: &[u8]);
+
+    /// Returns true once the driver has finished setting up the device and it has been activated.
+    fn is_activated(&self) -> bool;
+}